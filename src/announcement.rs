@@ -0,0 +1,78 @@
+use crate::database::announcement::AnnouncementEntry;
+use crate::database::contest::ContestId;
+use crate::database::problem::ProblemId;
+use crate::database::user::UserId;
+use crate::database::Database;
+use crate::request_handler::{create_html_response, RedirectSite};
+use crate::user::{decode_form_value, parse_body};
+use anyhow::{anyhow, bail, Result};
+use http_body_util::BodyExt;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::{Request, Response};
+use std::net::IpAddr;
+
+const MAX_ANNOUNCEMENT_LENGTH: usize = 2000;
+
+/// the announcements shown on the contest page or a problem page, they are marked as read once they were shown.
+/// announcements about problems are left out while the problems are hidden
+pub async fn get_page_announcements(database: &Database, contest_id: ContestId, problem_id: Option<ProblemId>, user: UserId, problems_hidden: bool) -> Result<Vec<AnnouncementEntry>> {
+    let mut announcements = database.get_announcements(contest_id, problem_id, user).await?;
+    if problems_hidden {
+        announcements.retain(|announcement| announcement.problem_id.is_none());
+    }
+
+    let new_announcements = announcements
+        .iter()
+        .filter(|announcement| announcement.is_new)
+        .map(|announcement| announcement.announcement_id)
+        .collect::<Vec<_>>();
+    database.mark_announcements_read(user, &new_announcements).await?;
+
+    Ok(announcements)
+}
+
+/// the announcement is about the whole contest if no problem is given
+pub async fn handle_announcement_posting(database: &Database, contest_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    let Ok(contest_id) = contest_id.parse::<ContestId>() else {
+        return Ok(None);
+    };
+    if !database.is_contest_id_valid(contest_id).await {
+        return Ok(None);
+    }
+
+    let body = request.into_body().collect().await?.to_bytes();
+    let body = String::from_utf8_lossy(&body).to_string();
+    let parsed_body = parse_body(&body);
+
+    let text = decode_form_value(parsed_body.get("text").ok_or_else(|| anyhow!("Announcement text not found"))?);
+    let text = text.trim();
+    if text.is_empty() {
+        bail!("Announcement cannot be empty");
+    }
+    if text.chars().count() > MAX_ANNOUNCEMENT_LENGTH {
+        bail!("Announcement cannot be longer than {MAX_ANNOUNCEMENT_LENGTH} characters");
+    }
+
+    let problem_id = match parsed_body.get("problem").map(String::as_str) {
+        None | Some("") => None,
+        Some(problem_id) => {
+            let problem_id = problem_id.parse::<ProblemId>()?;
+            if !database.get_problems_for_contest(contest_id).await?.contains(&problem_id) {
+                bail!("The problem is not part of the contest");
+            }
+            Some(problem_id)
+        }
+    };
+
+    let actor = database.get_audit_actor(user, ip).await?;
+    let transaction = database.begin_transaction().await?;
+    transaction.add_announcement(contest_id, problem_id, text).await?;
+    let after = problem_id.map_or_else(|| text.to_owned(), |problem_id| format!("problem {problem_id}: {text}"));
+    transaction.add_audit_entry(&actor, "post_announcement", &format!("contest {contest_id}"), "", &after).await?;
+    transaction.commit().await?;
+
+    Ok(Some(create_html_response(&RedirectSite {
+        url: format!("/contest/{contest_id}"),
+    })?))
+}
@@ -0,0 +1,40 @@
+use crate::database::audit::{AuditEntry, AuditFilter, AUDIT_LOG_PAGE_SIZE};
+use crate::database::user::UserId;
+use crate::database::Database;
+use crate::request_handler::create_html_response;
+use crate::sidebar::{create_sidebar_context, SidebarContext};
+use crate::user::parse_body;
+use anyhow::Result;
+use askama::Template;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::{Request, Response};
+
+#[derive(Template)]
+#[template(path = "audit_log.html")]
+pub struct AuditLogSite {
+    sidebar_context: SidebarContext,
+    entries: Vec<AuditEntry>,
+    users: Vec<(UserId, String)>,
+    actions: Vec<String>,
+    filter: AuditFilter,
+    page_size: i64,
+}
+
+/// the filters are passed in the query string, like `/audit_log?user=1&action=delete_user`
+pub async fn create_audit_log_page(database: &Database, user: UserId, request: &Request<Incoming>) -> Result<Response<Full<Bytes>>> {
+    let query = parse_body(request.uri().query().unwrap_or(""));
+    let filter = AuditFilter {
+        user_id: query.get("user").and_then(|user_id| user_id.parse().ok()),
+        action: query.get("action").cloned().unwrap_or_default(),
+    };
+
+    create_html_response(&AuditLogSite {
+        sidebar_context: create_sidebar_context(database, Some(user)).await?,
+        entries: database.get_audit_entries(&filter).await?,
+        users: database.get_audit_users().await?,
+        actions: database.get_audit_actions().await?,
+        filter,
+        page_size: AUDIT_LOG_PAGE_SIZE,
+    })
+}
@@ -0,0 +1,149 @@
+use crate::database::clarification::{ClarificationEntry, ClarificationId};
+use crate::database::contest::ContestId;
+use crate::database::problem::ProblemId;
+use crate::database::user::UserId;
+use crate::database::Database;
+use crate::request_handler::{create_html_response, RedirectSite};
+use crate::sidebar::{create_sidebar_context, SidebarContext};
+use crate::user::{decode_form_value, parse_body};
+use anyhow::{anyhow, bail, Result};
+use askama::Template;
+use chrono::Utc;
+use http_body_util::BodyExt;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::{Request, Response};
+use std::net::IpAddr;
+
+const MAX_CLARIFICATION_LENGTH: usize = 2000;
+
+#[derive(Template)]
+#[template(path = "clarifications.html")]
+pub struct ClarificationsSite {
+    sidebar_context: SidebarContext,
+    contest_id: ContestId,
+    contest_name: String,
+    is_admin: bool,
+    // the problems that can be asked about, empty while they are hidden
+    problems: Vec<(ProblemId, String, String)>,
+    selected_problem: Option<ProblemId>,
+    // the oldest unanswered question first, so admins answer them in order
+    unanswered: Vec<ClarificationEntry>,
+    answered: Vec<ClarificationEntry>,
+}
+
+/// participants ask questions and read the answers here, admins answer them.
+/// the problem can be preselected in the query string, like `/contest/1/clarifications?problem=2`
+pub async fn create_clarifications_page(database: &Database, contest_id: &str, user: UserId, request: &Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    let Ok(contest_id) = contest_id.parse::<ContestId>() else {
+        return Ok(None);
+    };
+    let is_admin = database.is_user_admin(user).await?;
+    if !database.is_contest_id_valid(contest_id).await || !(is_admin || database.is_user_in_contest(user, contest_id).await?) {
+        return Ok(None);
+    }
+
+    // seen before the sidebar is created, so it does not show the answers on this page as new
+    database.mark_clarifications_seen(user, contest_id, Utc::now()).await?;
+
+    let user_times = database.get_user_contest_times(user, contest_id).await?;
+    let problems = if is_admin || user_times.is_some_and(|times| times.has_started(Utc::now())) {
+        database.get_problem_names_for_contest(contest_id).await?
+    } else {
+        Vec::new()
+    };
+
+    let (mut unanswered, answered): (Vec<_>, Vec<_>) = database
+        .get_clarifications(contest_id, if is_admin { None } else { Some(user) })
+        .await?
+        .into_iter()
+        .partition(|clarification| clarification.answer.is_none());
+    unanswered.reverse();
+
+    Ok(Some(create_html_response(&ClarificationsSite {
+        sidebar_context: create_sidebar_context(database, Some(user)).await?,
+        contest_id,
+        contest_name: database.get_contest_name(contest_id).await?,
+        is_admin,
+        problems,
+        selected_problem: parse_body(request.uri().query().unwrap_or("")).get("problem").and_then(|problem_id| problem_id.parse().ok()),
+        unanswered,
+        answered,
+    })?))
+}
+
+fn validate_clarification_text(text: &str) -> Result<()> {
+    if text.is_empty() {
+        bail!("The text cannot be empty");
+    }
+    if text.chars().count() > MAX_CLARIFICATION_LENGTH {
+        bail!("The text cannot be longer than {MAX_CLARIFICATION_LENGTH} characters");
+    }
+    Ok(())
+}
+
+/// only participants can ask, the question is about the whole contest if no problem is given
+pub async fn handle_clarification_question(database: &Database, contest_id: &str, user: UserId, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    let Ok(contest_id) = contest_id.parse::<ContestId>() else {
+        return Ok(None);
+    };
+    if !database.is_contest_id_valid(contest_id).await || !database.is_user_in_contest(user, contest_id).await? {
+        return Ok(None);
+    }
+
+    let body = request.into_body().collect().await?.to_bytes();
+    let body = String::from_utf8_lossy(&body).to_string();
+    let parsed_body = parse_body(&body);
+
+    let question = decode_form_value(parsed_body.get("question").ok_or_else(|| anyhow!("Question not found"))?);
+    let question = question.trim();
+    validate_clarification_text(question)?;
+
+    let problem_id = match parsed_body.get("problem").map(String::as_str) {
+        None | Some("") => None,
+        Some(problem_id) => {
+            let problem_id = problem_id.parse::<ProblemId>()?;
+            if !database.get_problems_for_contest(contest_id).await?.contains(&problem_id) {
+                bail!("The problem is not part of the contest");
+            }
+            Some(problem_id)
+        }
+    };
+
+    database.add_clarification(user, contest_id, problem_id, question).await?;
+
+    Ok(Some(create_html_response(&RedirectSite {
+        url: format!("/contest/{contest_id}/clarifications"),
+    })?))
+}
+
+/// the answer goes only to the asker unless it is marked as public
+pub async fn handle_clarification_answer(database: &Database, contest_id: &str, clarification_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    let (Ok(contest_id), Ok(clarification_id)) = (contest_id.parse::<ContestId>(), clarification_id.parse::<ClarificationId>()) else {
+        return Ok(None);
+    };
+
+    let body = request.into_body().collect().await?.to_bytes();
+    let body = String::from_utf8_lossy(&body).to_string();
+    let parsed_body = parse_body(&body);
+
+    let answer = decode_form_value(parsed_body.get("answer").ok_or_else(|| anyhow!("Answer not found"))?);
+    let answer = answer.trim();
+    validate_clarification_text(answer)?;
+    let is_public = parsed_body.get("public").map(String::as_str) == Some("on");
+
+    let actor = database.get_audit_actor(user, ip).await?;
+    let transaction = database.begin_transaction().await?;
+    if !transaction.answer_clarification(contest_id, clarification_id, answer, is_public).await? {
+        bail!("Clarification {clarification_id} does not exist or was already answered");
+    }
+    let after = format!("{}: {answer}", if is_public { "public" } else { "private" });
+    transaction
+        .add_audit_entry(&actor, "answer_clarification", &format!("clarification {clarification_id}"), "", &after)
+        .await?;
+    transaction.commit().await?;
+
+    Ok(Some(create_html_response(&RedirectSite {
+        url: format!("/contest/{contest_id}/clarifications"),
+    })?))
+}
@@ -1,78 +1,162 @@
-use crate::database::contest::ContestId;
-use crate::database::problem::ProblemId;
+use crate::announcement::get_page_announcements;
+use crate::database::announcement::AnnouncementEntry;
+use crate::database::contest::{ContestId, ContestTimes, ContestType};
+use crate::database::problem::{get_letter_label, ProblemId};
 use crate::database::user::UserId;
 use crate::database::Database;
 use crate::request_handler::{create_html_response, RedirectSite};
 use crate::sidebar::{create_sidebar_context, SidebarContext};
-use crate::user::parse_body;
-use anyhow::Result;
+use crate::user::{decode_form_value, parse_body};
+use anyhow::{anyhow, bail, Result};
 use askama::Template;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use http_body_util::BodyExt;
 use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::{Request, Response};
+use std::net::IpAddr;
+
+/// the format of the datetime-local inputs, times are entered in UTC
+const TIME_INPUT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+/// what the countdown of a contest shows, the deadline is in milliseconds since the epoch, so the page can count down by itself
+pub struct ContestCountdown {
+    pub label: &'static str,
+    pub deadline: Option<i64>,
+}
+
+/// contests without a start and end time have no countdown
+pub fn get_contest_countdown(times: &ContestTimes, now: DateTime<Utc>) -> Option<ContestCountdown> {
+    if let Some(start_time) = times.start_time.filter(|_| !times.has_started(now)) {
+        return Some(ContestCountdown {
+            label: "Starts in",
+            deadline: Some(start_time.timestamp_millis()),
+        });
+    }
+    if times.has_ended(now) {
+        return Some(ContestCountdown { label: "Ended", deadline: None });
+    }
+    times.end_time.map(|end_time| ContestCountdown {
+        label: "Ends in",
+        deadline: Some(end_time.timestamp_millis()),
+    })
+}
+
+/// participants of windowed contests that did not start yet see when the window closes
+pub fn get_user_countdown(times: &ContestTimes, user_times: Option<&ContestTimes>, now: DateTime<Utc>) -> Option<ContestCountdown> {
+    match user_times {
+        Some(user_times) => get_contest_countdown(user_times, now),
+        None if times.is_running(now) => times.end_time.map(|end_time| ContestCountdown {
+            label: "Window closes in",
+            deadline: Some(end_time.timestamp_millis()),
+        }),
+        None => get_contest_countdown(times, now),
+    }
+}
+
+fn format_time(time: Option<DateTime<Utc>>, format: &str) -> String {
+    time.map(|time| time.format(format).to_string()).unwrap_or_default()
+}
 
 #[derive(Template)]
 #[template(path = "contest.html")]
 pub struct ContestSite {
     contest_name: String,
+    start_time: String,
+    end_time: String,
+    freeze_time: String,
+    duration_minutes: String,
+    countdown: Option<ContestCountdown>,
+    // a participant of a windowed contest that can start now
+    can_start: bool,
+    is_icpc: bool,
     is_admin: bool,
     users: Vec<(String, UserId, bool)>,
     contest_id: ContestId,
     sidebar_context: SidebarContext,
-    problems: Vec<(String, ProblemId)>,
+    problems: Vec<(ProblemId, String, String)>,
+    announcements: Vec<AnnouncementEntry>,
 }
 
 pub async fn create_contest_page(database: &Database, contest_id: &str, user: UserId) -> Result<Option<Response<Full<Bytes>>>> {
     if let Ok(contest_id) = contest_id.parse::<i32>() {
         if database.is_contest_id_valid(contest_id).await {
             let contest_name = database.get_contest_name(contest_id).await?;
+            let times = database.get_contest_times(contest_id).await?;
+            let user_times = database.get_user_contest_times(user, contest_id).await?;
             let is_admin = database.is_user_admin(user).await?;
+            let now = Utc::now();
 
-            let mut users = Vec::new();
-            for user_id in database.get_all_users().await? {
-                if let Some(username) = database.get_username(user_id).await? {
-                    let is_in_contest = database.is_user_in_contest(user_id, contest_id).await?;
-                    users.push((username, user_id, is_in_contest));
-                }
-            }
+            let users = database
+                .get_all_users_with_participation(contest_id)
+                .await?
+                .into_iter()
+                .map(|(user_id, username, is_in_contest)| (username, user_id, is_in_contest))
+                .collect();
 
-            let mut problems = Vec::new();
-            for problem_id in database.get_problems_for_contest(contest_id).await? {
-                let problem_name = database.get_problem_name(problem_id).await?;
-                problems.push((problem_name, problem_id));
-            }
+            let problems = database.get_problem_names_for_contest(contest_id).await?;
+            let problems_hidden = !is_admin && !user_times.as_ref().is_some_and(|user_times| user_times.has_started(now));
+            let announcements = get_page_announcements(database, contest_id, None, user, problems_hidden).await?;
 
             return Ok(Some(create_html_response(&ContestSite {
                 contest_name,
+                start_time: format_time(times.start_time, TIME_INPUT_FORMAT),
+                end_time: format_time(times.end_time, TIME_INPUT_FORMAT),
+                freeze_time: format_time(times.freeze_time, TIME_INPUT_FORMAT),
+                duration_minutes: times.duration_minutes.map(|minutes| minutes.to_string()).unwrap_or_default(),
+                countdown: get_user_countdown(&times, user_times.as_ref(), now),
+                can_start: user_times.is_none() && times.is_running(now) && database.is_user_in_contest(user, contest_id).await?,
+                is_icpc: database.get_contest_type(contest_id).await? == ContestType::Icpc,
                 is_admin,
                 users,
                 contest_id,
                 sidebar_context: create_sidebar_context(database, Some(user)).await?,
                 problems,
+                announcements,
             })?));
         }
     }
     Ok(None)
 }
 
-pub async fn handle_participant_modification(database: &Database, contest_id: &str, user: UserId, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+/// the usernames of all participants of a contest, used for the audit log
+async fn get_participants_summary(database: &Database, contest_id: ContestId) -> Result<String> {
+    Ok(database
+        .get_all_users_with_participation(contest_id)
+        .await?
+        .into_iter()
+        .filter(|(_, _, is_in_contest)| *is_in_contest)
+        .map(|(_, username, _)| username)
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+pub async fn handle_participant_modification(database: &Database, contest_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
     if let Ok(contest_id) = contest_id.parse::<i32>() {
         if database.is_contest_id_valid(contest_id).await && database.is_user_admin(user).await? {
             let body = request.into_body().collect().await?.to_bytes();
             let body = String::from_utf8_lossy(&body).to_string();
             let parsed_body = parse_body(&body);
 
-            let all_users = database.get_all_users().await?;
+            let actor = database.get_audit_actor(user, ip).await?;
+            let transaction = database.begin_transaction().await?;
+            let before = get_participants_summary(&transaction, contest_id).await?;
+
+            let all_users = transaction.get_all_users().await?;
             for user_id in all_users {
                 if let Some(action) = parsed_body.get(&format!("user_{user_id}")) {
                     if action == "on" {
-                        database.add_user_to_contest(user_id, contest_id).await?;
+                        transaction.add_user_to_contest(user_id, contest_id).await?;
                     } else {
-                        database.remove_user_from_contest(user_id, contest_id).await?;
+                        transaction.remove_user_from_contest(user_id, contest_id).await?;
                     }
                 }
             }
+
+            let after = get_participants_summary(&transaction, contest_id).await?;
+            transaction.add_audit_entry(&actor, "modify_participants", &format!("contest {contest_id}"), &before, &after).await?;
+            transaction.commit().await?;
+
             return Ok(Some(create_html_response(&RedirectSite {
                 url: format!("/contest/{contest_id}"),
             })?));
@@ -81,13 +165,374 @@ pub async fn handle_participant_modification(database: &Database, contest_id: &s
     Ok(None)
 }
 
-pub async fn handle_problem_deletion_from_contest(database: &Database, contest_id: &str, problem_id: &str) -> Result<Response<Full<Bytes>>> {
+/// the form has to be submitted with the confirmation checkbox ticked, the problem itself is kept
+pub async fn handle_problem_deletion_from_contest(database: &Database, contest_id: &str, problem_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Response<Full<Bytes>>> {
     let contest_id = contest_id.parse::<i32>()?;
     let problem_id = problem_id.parse::<i32>()?;
 
-    database.remove_problem_from_contest(contest_id, problem_id).await?;
+    let body = request.into_body().collect().await?.to_bytes();
+    let body = String::from_utf8_lossy(&body).to_string();
+    if parse_body(&body).get("confirm").map(String::as_str) != Some("on") {
+        return create_html_response(&RedirectSite {
+            url: format!("/contest/{contest_id}"),
+        });
+    }
+
+    let actor = database.get_audit_actor(user, ip).await?;
+    let transaction = database.begin_transaction().await?;
+    transaction.remove_problem_from_contest(contest_id, problem_id).await?;
+    transaction
+        .add_audit_entry(&actor, "remove_problem_from_contest", &format!("contest {contest_id}"), &format!("problem {problem_id}"), "")
+        .await?;
+    transaction.commit().await?;
+
+    create_html_response(&RedirectSite {
+        url: format!("/contest/{contest_id}"),
+    })
+}
+
+/// the order of the problems with their labels for the audit log
+async fn get_problem_order_summary(database: &Database, contest_id: ContestId) -> Result<String> {
+    Ok(database
+        .get_problem_names_for_contest(contest_id)
+        .await?
+        .into_iter()
+        .map(|(_, label, problem_name)| format!("{label}: {problem_name}"))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+/// swaps the problem with the one before or after it
+pub async fn handle_problem_move(database: &Database, contest_id: &str, problem_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    let (Ok(contest_id), Ok(problem_id)) = (contest_id.parse::<ContestId>(), problem_id.parse::<ProblemId>()) else {
+        return Ok(None);
+    };
+    let problems = database
+        .get_problem_names_for_contest(contest_id)
+        .await?
+        .into_iter()
+        .map(|(problem_id, label, _)| (problem_id, label))
+        .collect::<Vec<_>>();
+    let Some(index) = problems.iter().position(|(id, _)| *id == problem_id) else {
+        return Ok(None);
+    };
+
+    let body = request.into_body().collect().await?.to_bytes();
+    let body = String::from_utf8_lossy(&body).to_string();
+    let other = match parse_body(&body).get("direction").map(String::as_str) {
+        Some("up") => index.checked_sub(1),
+        Some("down") => Some(index + 1).filter(|other| *other < problems.len()),
+        _ => bail!("Invalid direction"),
+    };
+
+    if let Some(other) = other {
+        let actor = database.get_audit_actor(user, ip).await?;
+        let transaction = database.begin_transaction().await?;
+        let before = get_problem_order_summary(&transaction, contest_id).await?;
+        // the labels belong to the positions, so only the problems are swapped
+        let mut problem_ids = problems.iter().map(|(problem_id, _)| *problem_id).collect::<Vec<_>>();
+        problem_ids.swap(index, other);
+        let problems = problem_ids.into_iter().zip(problems.into_iter().map(|(_, label)| label)).collect::<Vec<_>>();
+        transaction.set_problem_order(contest_id, &problems).await?;
+        transaction
+            .add_audit_entry(
+                &actor,
+                "move_problem",
+                &format!("contest {contest_id}"),
+                &before,
+                &get_problem_order_summary(&transaction, contest_id).await?,
+            )
+            .await?;
+        transaction.commit().await?;
+    }
+
+    Ok(Some(create_html_response(&RedirectSite {
+        url: format!("/contest/{contest_id}"),
+    })?))
+}
+
+/// labels the problems in their order with letters or numbers
+pub async fn handle_problem_labeling(database: &Database, contest_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    let Ok(contest_id) = contest_id.parse::<ContestId>() else {
+        return Ok(None);
+    };
+    if !database.is_contest_id_valid(contest_id).await {
+        return Ok(None);
+    }
+
+    let body = request.into_body().collect().await?.to_bytes();
+    let body = String::from_utf8_lossy(&body).to_string();
+    let use_numbers = match parse_body(&body).get("style").map(String::as_str) {
+        Some("letters") => false,
+        Some("numbers") => true,
+        _ => bail!("Invalid label style"),
+    };
+
+    let actor = database.get_audit_actor(user, ip).await?;
+    let transaction = database.begin_transaction().await?;
+    let before = get_problem_order_summary(&transaction, contest_id).await?;
+    let problems = transaction
+        .get_problems_for_contest(contest_id)
+        .await?
+        .into_iter()
+        .enumerate()
+        .map(|(position, problem_id)| (problem_id, if use_numbers { (position + 1).to_string() } else { get_letter_label(position) }))
+        .collect::<Vec<_>>();
+    transaction.set_problem_order(contest_id, &problems).await?;
+    transaction
+        .add_audit_entry(
+            &actor,
+            "label_problems",
+            &format!("contest {contest_id}"),
+            &before,
+            &get_problem_order_summary(&transaction, contest_id).await?,
+        )
+        .await?;
+    transaction.commit().await?;
+
+    Ok(Some(create_html_response(&RedirectSite {
+        url: format!("/contest/{contest_id}"),
+    })?))
+}
+
+/// the longest name the contests table can store
+const MAX_CONTEST_NAME_LENGTH: usize = 100;
+
+/// contest names have to be unique, the contest itself can keep its name when it is renamed
+async fn validate_contest_name(database: &Database, contest_name: &str, contest_id: Option<ContestId>) -> Result<()> {
+    if contest_name.is_empty() {
+        bail!("Contest name cannot be empty");
+    }
+    if contest_name.chars().count() > MAX_CONTEST_NAME_LENGTH {
+        bail!("Contest name cannot be longer than {MAX_CONTEST_NAME_LENGTH} characters");
+    }
+    if let Some(existing) = database.get_contest_from_name(contest_name).await? {
+        if Some(existing) != contest_id {
+            bail!("Contest \"{contest_name}\" already exists");
+        }
+    }
+    Ok(())
+}
+
+pub async fn handle_contest_creation(database: &Database, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Response<Full<Bytes>>> {
+    let body = request.into_body().collect().await?.to_bytes();
+    let body = String::from_utf8_lossy(&body).to_string();
+    let parsed_body = parse_body(&body);
+
+    let contest_name = decode_form_value(parsed_body.get("contest_name").ok_or_else(|| anyhow!("Contest name not found"))?);
+    let contest_name = contest_name.trim();
+    validate_contest_name(database, contest_name, None).await?;
+
+    let actor = database.get_audit_actor(user, ip).await?;
+    let transaction = database.begin_transaction().await?;
+    let contest_id = transaction.add_contest(contest_name).await?;
+    transaction.add_audit_entry(&actor, "create_contest", &format!("contest {contest_id}"), "", contest_name).await?;
+    transaction.commit().await?;
 
     create_html_response(&RedirectSite {
         url: format!("/contest/{contest_id}"),
     })
 }
+
+pub async fn handle_contest_renaming(database: &Database, contest_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    if let Ok(contest_id) = contest_id.parse::<ContestId>() {
+        if database.is_contest_id_valid(contest_id).await {
+            let body = request.into_body().collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(&body).to_string();
+            let parsed_body = parse_body(&body);
+
+            let contest_name = decode_form_value(parsed_body.get("contest_name").ok_or_else(|| anyhow!("Contest name not found"))?);
+            let contest_name = contest_name.trim();
+            validate_contest_name(database, contest_name, Some(contest_id)).await?;
+
+            let actor = database.get_audit_actor(user, ip).await?;
+            let transaction = database.begin_transaction().await?;
+            let before = transaction.get_contest_name(contest_id).await?;
+            transaction.set_contest_name(contest_id, contest_name).await?;
+            transaction.add_audit_entry(&actor, "rename_contest", &format!("contest {contest_id}"), &before, contest_name).await?;
+            transaction.commit().await?;
+
+            return Ok(Some(create_html_response(&RedirectSite {
+                url: format!("/contest/{contest_id}"),
+            })?));
+        }
+    }
+    Ok(None)
+}
+
+/// the problems of the contest are kept, they can still be added to other contests
+pub async fn handle_contest_deletion(database: &Database, contest_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    if let Ok(contest_id) = contest_id.parse::<ContestId>() {
+        if database.is_contest_id_valid(contest_id).await {
+            let body = request.into_body().collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(&body).to_string();
+            if parse_body(&body).get("confirm").map(String::as_str) != Some("on") {
+                return Ok(Some(create_html_response(&RedirectSite {
+                    url: format!("/contest/{contest_id}"),
+                })?));
+            }
+
+            let actor = database.get_audit_actor(user, ip).await?;
+            let transaction = database.begin_transaction().await?;
+            let before = format!(
+                "{}, participants: {}",
+                transaction.get_contest_name(contest_id).await?,
+                get_participants_summary(&transaction, contest_id).await?
+            );
+            transaction.remove_contest(contest_id).await?;
+            transaction.add_audit_entry(&actor, "delete_contest", &format!("contest {contest_id}"), &before, "").await?;
+            transaction.commit().await?;
+
+            return Ok(Some(create_html_response(&RedirectSite { url: "/".to_owned() })?));
+        }
+    }
+    Ok(None)
+}
+
+/// a summary of the contest times for the audit log
+fn get_times_summary(times: &ContestTimes) -> String {
+    let start_time = format_time(times.start_time, "%Y-%m-%d %H:%M");
+    let end_time = format_time(times.end_time, "%Y-%m-%d %H:%M");
+    let freeze_time = format_time(times.freeze_time, "%Y-%m-%d %H:%M");
+    let duration = times.duration_minutes.map(|minutes| format!("{minutes} minutes")).unwrap_or_default();
+    format!(
+        "start: {}, end: {}, freeze: {}, duration: {}",
+        if start_time.is_empty() { "none" } else { &start_time },
+        if end_time.is_empty() { "none" } else { &end_time },
+        if freeze_time.is_empty() { "none" } else { &freeze_time },
+        if duration.is_empty() { "none" } else { &duration }
+    )
+}
+
+/// an empty input means that there is no limit
+fn parse_time_input(input: Option<&String>) -> Result<Option<DateTime<Utc>>> {
+    let input = decode_form_value(input.map_or("", String::as_str));
+    if input.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(NaiveDateTime::parse_from_str(&input, TIME_INPUT_FORMAT)?.and_utc()))
+}
+
+pub async fn handle_contest_times_modification(database: &Database, contest_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    if let Ok(contest_id) = contest_id.parse::<ContestId>() {
+        if database.is_contest_id_valid(contest_id).await {
+            let body = request.into_body().collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(&body).to_string();
+            let parsed_body = parse_body(&body);
+
+            let times = ContestTimes {
+                start_time: parse_time_input(parsed_body.get("start_time"))?,
+                end_time: parse_time_input(parsed_body.get("end_time"))?,
+                freeze_time: parse_time_input(parsed_body.get("freeze_time"))?,
+                duration_minutes: parsed_body.get("duration_minutes").filter(|minutes| !minutes.is_empty()).map(|minutes| minutes.parse()).transpose()?,
+            };
+            if times.duration_minutes.is_some_and(|minutes| minutes <= 0) {
+                bail!("The duration of a windowed contest has to be positive");
+            }
+            if let (Some(start_time), Some(end_time)) = (times.start_time, times.end_time) {
+                if start_time >= end_time {
+                    bail!("The contest has to start before it ends");
+                }
+            }
+            if let Some(freeze_time) = times.freeze_time {
+                if times.start_time.is_some_and(|start_time| freeze_time < start_time) || times.end_time.is_none_or(|end_time| freeze_time >= end_time) {
+                    bail!("The scoreboard has to be frozen during the contest");
+                }
+            }
+
+            let actor = database.get_audit_actor(user, ip).await?;
+            let transaction = database.begin_transaction().await?;
+            let before = transaction.get_contest_times(contest_id).await?;
+            transaction.set_contest_times(contest_id, times).await?;
+            transaction
+                .add_audit_entry(&actor, "set_contest_times", &format!("contest {contest_id}"), &get_times_summary(&before), &get_times_summary(&times))
+                .await?;
+            transaction.commit().await?;
+
+            return Ok(Some(create_html_response(&RedirectSite {
+                url: format!("/contest/{contest_id}"),
+            })?));
+        }
+    }
+    Ok(None)
+}
+
+/// moves the end of the contest back by the given number of minutes, see `ContestTimes::extended_by`
+pub async fn handle_contest_extension(database: &Database, contest_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    if let Ok(contest_id) = contest_id.parse::<ContestId>() {
+        if database.is_contest_id_valid(contest_id).await {
+            let body = request.into_body().collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(&body).to_string();
+            let minutes = parse_body(&body).get("minutes").ok_or_else(|| anyhow!("Minutes not found"))?.parse::<i32>()?;
+            if minutes <= 0 {
+                bail!("A contest can only be extended by a positive number of minutes");
+            }
+
+            let actor = database.get_audit_actor(user, ip).await?;
+            let transaction = database.begin_transaction().await?;
+            let before = transaction.get_contest_times(contest_id).await?;
+            let after = before.extended_by(minutes)?;
+            transaction.set_contest_times(contest_id, after).await?;
+            transaction
+                .add_audit_entry(&actor, "extend_contest", &format!("contest {contest_id}"), &get_times_summary(&before), &get_times_summary(&after))
+                .await?;
+            transaction.commit().await?;
+
+            return Ok(Some(create_html_response(&RedirectSite {
+                url: format!("/contest/{contest_id}"),
+            })?));
+        }
+    }
+    Ok(None)
+}
+
+pub async fn handle_contest_type_modification(database: &Database, contest_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    if let Ok(contest_id) = contest_id.parse::<ContestId>() {
+        if database.is_contest_id_valid(contest_id).await {
+            let body = request.into_body().collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(&body).to_string();
+            let contest_type = match parse_body(&body).get("contest_type").map(String::as_str) {
+                Some("ioi") => ContestType::Ioi,
+                Some("icpc") => ContestType::Icpc,
+                _ => bail!("Invalid contest type"),
+            };
+
+            let actor = database.get_audit_actor(user, ip).await?;
+            let transaction = database.begin_transaction().await?;
+            let before = transaction.get_contest_type(contest_id).await?;
+            transaction.set_contest_type(contest_id, contest_type).await?;
+            transaction
+                .add_audit_entry(&actor, "set_contest_type", &format!("contest {contest_id}"), &format!("{before:?}"), &format!("{contest_type:?}"))
+                .await?;
+            transaction.commit().await?;
+
+            return Ok(Some(create_html_response(&RedirectSite {
+                url: format!("/contest/{contest_id}"),
+            })?));
+        }
+    }
+    Ok(None)
+}
+
+/// starts a windowed contest for a participant, the personal time runs from now on
+pub async fn handle_contest_start(database: &Database, contest_id: &str, user: UserId) -> Result<Option<Response<Full<Bytes>>>> {
+    if let Ok(contest_id) = contest_id.parse::<ContestId>() {
+        if database.is_contest_id_valid(contest_id).await && database.is_user_in_contest(user, contest_id).await? {
+            let times = database.get_contest_times(contest_id).await?;
+            let now = Utc::now();
+            if !times.is_windowed() {
+                bail!("The contest has no personal start times");
+            }
+            if !times.is_running(now) {
+                bail!("The contest window is not open");
+            }
+
+            database.start_participation(user, contest_id, now).await?;
+
+            return Ok(Some(create_html_response(&RedirectSite {
+                url: format!("/contest/{contest_id}"),
+            })?));
+        }
+    }
+    Ok(None)
+}
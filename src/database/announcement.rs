@@ -0,0 +1,68 @@
+use crate::database::contest::ContestId;
+use crate::database::problem::{get_problem_title, ProblemId};
+use crate::database::user::UserId;
+use crate::database::{Database, DatabaseQuery};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+
+pub type AnnouncementId = i32;
+
+pub struct AnnouncementEntry {
+    pub announcement_id: AnnouncementId,
+    pub problem_id: Option<ProblemId>,
+    // the label and name of the problem, empty for announcements about the whole contest
+    pub problem: String,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    // the user has not seen the announcement before
+    pub is_new: bool,
+}
+
+impl Database {
+    pub async fn add_announcement(&self, contest_id: ContestId, problem_id: Option<ProblemId>, text: &str) -> Result<AnnouncementId> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO announcements (contest_id, problem_id, announcement_text, created_at) VALUES ($1, $2, $3, $4) RETURNING announcement_id");
+
+        Ok(QUERY
+            .execute(self, &[&contest_id, &problem_id, &text, &Utc::now()])
+            .await?
+            .first()
+            .ok_or_else(|| anyhow!("No announcement id returned"))?
+            .get(0))
+    }
+
+    /// the newest announcements of the contest first, with a problem only the ones about the whole contest or that problem
+    pub async fn get_announcements(&self, contest_id: ContestId, problem_id: Option<ProblemId>, user_id: UserId) -> Result<Vec<AnnouncementEntry>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT announcements.announcement_id, announcements.problem_id, contest_problems.label, problems.problem_name, announcements.announcement_text, announcements.created_at,
+                NOT EXISTS (SELECT 1 FROM announcement_reads WHERE announcement_reads.announcement_id = announcements.announcement_id AND announcement_reads.user_id = $3)
+            FROM announcements
+            LEFT JOIN problems ON problems.problem_id = announcements.problem_id
+            LEFT JOIN contest_problems ON contest_problems.contest_id = announcements.contest_id AND contest_problems.problem_id = announcements.problem_id
+            WHERE announcements.contest_id = $1 AND ($2 = 0 OR announcements.problem_id IS NULL OR announcements.problem_id = $2)
+            ORDER BY announcements.announcement_id DESC",
+        );
+
+        Ok(QUERY
+            .execute(self, &[&contest_id, &problem_id.unwrap_or(0), &user_id])
+            .await?
+            .iter()
+            .map(|row| AnnouncementEntry {
+                announcement_id: row.get(0),
+                problem_id: row.get(1),
+                problem: get_problem_title(row.get(2), row.get(3)),
+                text: row.get(4),
+                created_at: row.get(5),
+                is_new: row.get(6),
+            })
+            .collect())
+    }
+
+    pub async fn mark_announcements_read(&self, user_id: UserId, announcements: &[AnnouncementId]) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO announcement_reads (announcement_id, user_id) VALUES ($1, $2) ON CONFLICT DO NOTHING");
+
+        for announcement_id in announcements {
+            QUERY.execute(self, &[announcement_id, &user_id]).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use crate::database::user::UserId;
+use crate::database::{Database, DatabaseQuery};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::net::IpAddr;
+
+/// how many entries the audit log page shows at most
+pub const AUDIT_LOG_PAGE_SIZE: i64 = 500;
+
+/// a single administrative action
+pub struct AuditEntry {
+    pub username: String,
+    pub action: String,
+    pub target: String,
+    pub ip_address: String,
+    pub before_summary: String,
+    pub after_summary: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// which entries of the audit log to show, empty fields match everything
+#[derive(Default)]
+pub struct AuditFilter {
+    pub user_id: Option<UserId>,
+    pub action: String,
+}
+
+/// the admin who performs an action, the username is stored with the entry so that it stays readable after the admin is deleted
+pub struct AuditActor {
+    pub user_id: UserId,
+    pub username: String,
+    pub ip_address: IpAddr,
+}
+
+impl Database {
+    /// has to be looked up before the action, since the action might delete the admin
+    pub async fn get_audit_actor(&self, user_id: UserId, ip_address: IpAddr) -> Result<AuditActor> {
+        Ok(AuditActor {
+            user_id,
+            username: self.get_username(user_id).await?.unwrap_or_default(),
+            ip_address,
+        })
+    }
+
+    /// records an action that an admin performed, before and after are short human readable summaries of what changed
+    pub async fn add_audit_entry(&self, actor: &AuditActor, action: &str, target: &str, before_summary: &str, after_summary: &str) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "INSERT INTO audit_log (user_id, username, action, target, ip_address, before_summary, after_summary, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        );
+
+        QUERY
+            .execute(
+                self,
+                &[
+                    &actor.user_id,
+                    &actor.username,
+                    &action,
+                    &target,
+                    &actor.ip_address.to_string(),
+                    &before_summary,
+                    &after_summary,
+                    &Utc::now(),
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// the newest entries first
+    pub async fn get_audit_entries(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT username, action, target, ip_address, before_summary, after_summary, created_at FROM audit_log
+            WHERE ($1 = 0 OR user_id = $1) AND ($2 = '' OR action = $2)
+            ORDER BY audit_id DESC LIMIT $3",
+        );
+
+        Ok(QUERY
+            .execute(self, &[&filter.user_id.unwrap_or(0), &filter.action, &AUDIT_LOG_PAGE_SIZE])
+            .await?
+            .iter()
+            .map(|row| AuditEntry {
+                username: row.get(0),
+                action: row.get(1),
+                target: row.get(2),
+                ip_address: row.get(3),
+                before_summary: row.get(4),
+                after_summary: row.get(5),
+                created_at: row.get(6),
+            })
+            .collect())
+    }
+
+    /// all users that appear in the audit log and still exist, used for filtering
+    pub async fn get_audit_users(&self) -> Result<Vec<(UserId, String)>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT DISTINCT user_id, username FROM audit_log WHERE user_id IS NOT NULL ORDER BY username");
+
+        Ok(QUERY.execute(self, &[]).await?.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    pub async fn get_audit_actions(&self) -> Result<Vec<String>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT DISTINCT action FROM audit_log ORDER BY action");
+
+        Ok(QUERY.execute(self, &[]).await?.iter().map(|row| row.get(0)).collect())
+    }
+}
@@ -0,0 +1,639 @@
+use crate::database::storage::Backend;
+use crate::database::{Database, DatabaseQuery};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use std::io::{Read, Write};
+
+// every backup starts with these bytes, followed by the format version
+const BACKUP_MAGIC: &[u8; 9] = b"CPSBACKUP";
+// has to be increased whenever the layout of `Backup` changes
+const BACKUP_FORMAT_VERSION: u32 = 12;
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct UserRow {
+    pub user_id: i32,
+    pub username: String,
+    pub password: String,
+    pub is_admin: bool,
+    pub is_team: bool,
+    pub team_id: Option<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct ContestRow {
+    pub contest_id: i32,
+    pub contest_name: String,
+    // microseconds since the epoch
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub contest_type: i32,
+    pub freeze_time: Option<i64>,
+    pub revealed_until: Option<i32>,
+    pub duration_minutes: Option<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct ProblemRow {
+    pub problem_id: i32,
+    pub problem_name: String,
+    pub problem_description: String,
+    pub points: i32,
+    pub time_limit: i32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct SubtaskRow {
+    pub subtask_id: i32,
+    pub problem_id: Option<i32>,
+    pub subtask_score: i32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct TestRow {
+    pub test_id: i32,
+    pub problem_id: Option<i32>,
+    pub input_data: String,
+    pub output_data: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct SubmissionRow {
+    pub submission_id: i32,
+    pub user_id: Option<i32>,
+    pub contest_id: Option<i32>,
+    pub problem_id: Option<i32>,
+    pub code: String,
+    pub result: i32,
+    pub points: Option<i32>,
+    pub tests_done: i32,
+    // microseconds since the epoch
+    pub submitted_at: Option<i64>,
+    pub submitted_by: Option<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct SubtaskResultRow {
+    pub submission_id: Option<i32>,
+    pub subtask_id: Option<i32>,
+    pub points: Option<i32>,
+    pub result: i32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct TestResultRow {
+    pub submission_id: Option<i32>,
+    pub test_id: Option<i32>,
+    pub result: i32,
+    pub time: Option<i32>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct AuditRow {
+    pub audit_id: i32,
+    pub user_id: Option<i32>,
+    pub username: String,
+    pub action: String,
+    pub target: String,
+    pub ip_address: String,
+    pub before_summary: String,
+    pub after_summary: String,
+    /// microseconds since the unix epoch
+    pub created_at: i64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct ClarificationRow {
+    pub clarification_id: i32,
+    pub contest_id: i32,
+    pub problem_id: Option<i32>,
+    pub user_id: i32,
+    pub question: String,
+    // microseconds since the epoch
+    pub asked_at: i64,
+    pub answer: Option<String>,
+    pub answered_at: Option<i64>,
+    pub is_public: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct AnnouncementRow {
+    pub announcement_id: i32,
+    pub contest_id: i32,
+    pub problem_id: Option<i32>,
+    pub announcement_text: String,
+    // microseconds since the epoch
+    pub created_at: i64,
+}
+
+/// the whole content of a database, including the audit log, login tokens are left out, so everyone has to log in again after a restore
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Debug)]
+pub struct Backup {
+    /// a backup can only be restored into a database with the same schema
+    pub schema_version: i32,
+    pub users: Vec<UserRow>,
+    pub contests: Vec<ContestRow>,
+    // contest id, user id, the personal start and when the clarifications were last seen in microseconds since the epoch
+    pub contest_participations: Vec<(i32, i32, Option<i64>, Option<i64>)>,
+    pub problems: Vec<ProblemRow>,
+    // contest id, problem id, position and label
+    pub contest_problems: Vec<(i32, i32, i32, String)>,
+    // user id, contest id, problem id, score and the improvement time in microseconds since the epoch
+    pub user_problem_scores: Vec<(i32, i32, i32, i32, Option<i64>)>,
+    pub subtasks: Vec<SubtaskRow>,
+    pub tests: Vec<TestRow>,
+    pub subtask_tests: Vec<(i32, i32)>,
+    pub submissions: Vec<SubmissionRow>,
+    pub subtask_results: Vec<SubtaskResultRow>,
+    pub test_results: Vec<TestResultRow>,
+    pub audit_log: Vec<AuditRow>,
+    pub clarifications: Vec<ClarificationRow>,
+    pub announcements: Vec<AnnouncementRow>,
+    // announcement id and user id
+    pub announcement_reads: Vec<(i32, i32)>,
+}
+
+impl Backup {
+    /// writes the backup as a single compressed archive
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(BACKUP_MAGIC)?;
+        writer.write_all(&BACKUP_FORMAT_VERSION.to_le_bytes())?;
+
+        let mut encoder = snap::write::FrameEncoder::new(writer);
+        bincode::serialize_into(&mut encoder, self)?;
+        encoder.flush()?;
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Self> {
+        let mut magic = [0; BACKUP_MAGIC.len()];
+        reader.read_exact(&mut magic).map_err(|_err| anyhow!("This is not a CPS backup"))?;
+        if &magic != BACKUP_MAGIC {
+            bail!("This is not a CPS backup");
+        }
+
+        let mut format_version = [0; 4];
+        reader.read_exact(&mut format_version)?;
+        let format_version = u32::from_le_bytes(format_version);
+        if format_version != BACKUP_FORMAT_VERSION {
+            bail!("The backup has format version {format_version}, but this version of CPS can only read version {BACKUP_FORMAT_VERSION}");
+        }
+
+        Ok(bincode::deserialize_from(snap::read::FrameDecoder::new(reader))?)
+    }
+}
+
+impl Database {
+    /// reads everything from the database, all tables are read in one transaction, so the backup is consistent
+    #[allow(clippy::too_many_lines, reason = "one query for every table")]
+    pub async fn create_backup(&self) -> Result<Backup> {
+        static USERS_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT user_id, username, password, is_admin, is_team, team_id FROM users ORDER BY user_id");
+        static CONTESTS_QUERY: DatabaseQuery =
+            DatabaseQuery::new("SELECT contest_id, contest_name, start_time, end_time, contest_type, freeze_time, revealed_until, duration_minutes FROM contests ORDER BY contest_id");
+        static CONTEST_PARTICIPATIONS_QUERY: DatabaseQuery =
+            DatabaseQuery::new("SELECT contest_id, user_id, started_at, clarifications_seen_at FROM contest_participations ORDER BY contest_id, user_id");
+        static PROBLEMS_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT problem_id, problem_name, problem_description, points, time_limit FROM problems ORDER BY problem_id");
+        static CONTEST_PROBLEMS_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT contest_id, problem_id, position, label FROM contest_problems ORDER BY contest_id, problem_id");
+        static USER_PROBLEM_SCORES_QUERY: DatabaseQuery =
+            DatabaseQuery::new("SELECT user_id, contest_id, problem_id, score, improved_at FROM user_problem_scores ORDER BY user_id, contest_id, problem_id");
+        static SUBTASKS_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT subtask_id, problem_id, subtask_score FROM subtasks ORDER BY subtask_id");
+        static TESTS_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT test_id, problem_id, input_data, output_data FROM tests ORDER BY test_id");
+        static SUBTASK_TESTS_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT subtask_id, test_id FROM subtask_tests ORDER BY subtask_id, test_id");
+        static SUBMISSIONS_QUERY: DatabaseQuery =
+            DatabaseQuery::new("SELECT submission_id, user_id, contest_id, problem_id, code, result, points, tests_done, submitted_at, submitted_by FROM submissions ORDER BY submission_id");
+        static SUBTASK_RESULTS_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT submission_id, subtask_id, points, result FROM subtask_results ORDER BY submission_id, subtask_id");
+        static TEST_RESULTS_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT submission_id, test_id, result, time FROM test_results ORDER BY submission_id, test_id");
+        static CLARIFICATIONS_QUERY: DatabaseQuery =
+            DatabaseQuery::new("SELECT clarification_id, contest_id, problem_id, user_id, question, asked_at, answer, answered_at, is_public FROM clarifications ORDER BY clarification_id");
+        static ANNOUNCEMENTS_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT announcement_id, contest_id, problem_id, announcement_text, created_at FROM announcements ORDER BY announcement_id");
+        static ANNOUNCEMENT_READS_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT announcement_id, user_id FROM announcement_reads ORDER BY announcement_id, user_id");
+        static AUDIT_LOG_QUERY: DatabaseQuery =
+            DatabaseQuery::new("SELECT audit_id, user_id, username, action, target, ip_address, before_summary, after_summary, created_at FROM audit_log ORDER BY audit_id");
+
+        let transaction = self.begin_transaction().await?;
+
+        // by default every postgres statement sees the data at the time it starts, not when the transaction started
+        if self.backend() == Backend::Postgres {
+            transaction.batch_execute("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ").await?;
+        }
+
+        let backup = Backup {
+            schema_version: transaction.get_schema_version().await?,
+            users: USERS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| UserRow {
+                    user_id: row.get(0),
+                    username: row.get(1),
+                    password: row.get(2),
+                    is_admin: row.get(3),
+                    is_team: row.get(4),
+                    team_id: row.get(5),
+                })
+                .collect(),
+            contests: CONTESTS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| ContestRow {
+                    contest_id: row.get(0),
+                    contest_name: row.get(1),
+                    start_time: row.get::<Option<DateTime<Utc>>>(2).map(|time| time.timestamp_micros()),
+                    end_time: row.get::<Option<DateTime<Utc>>>(3).map(|time| time.timestamp_micros()),
+                    contest_type: row.get(4),
+                    freeze_time: row.get::<Option<DateTime<Utc>>>(5).map(|time| time.timestamp_micros()),
+                    revealed_until: row.get(6),
+                    duration_minutes: row.get(7),
+                })
+                .collect(),
+            contest_participations: CONTEST_PARTICIPATIONS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| {
+                    (
+                        row.get(0),
+                        row.get(1),
+                        row.get::<Option<DateTime<Utc>>>(2).map(|time| time.timestamp_micros()),
+                        row.get::<Option<DateTime<Utc>>>(3).map(|time| time.timestamp_micros()),
+                    )
+                })
+                .collect(),
+            problems: PROBLEMS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| ProblemRow {
+                    problem_id: row.get(0),
+                    problem_name: row.get(1),
+                    problem_description: row.get(2),
+                    points: row.get(3),
+                    time_limit: row.get(4),
+                })
+                .collect(),
+            contest_problems: CONTEST_PROBLEMS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3)))
+                .collect(),
+            user_problem_scores: USER_PROBLEM_SCORES_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| (row.get(0), row.get(1), row.get(2), row.get(3), row.get::<Option<DateTime<Utc>>>(4).map(|time| time.timestamp_micros())))
+                .collect(),
+            subtasks: SUBTASKS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| SubtaskRow {
+                    subtask_id: row.get(0),
+                    problem_id: row.get(1),
+                    subtask_score: row.get(2),
+                })
+                .collect(),
+            tests: TESTS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| TestRow {
+                    test_id: row.get(0),
+                    problem_id: row.get(1),
+                    input_data: row.get(2),
+                    output_data: row.get(3),
+                })
+                .collect(),
+            subtask_tests: SUBTASK_TESTS_QUERY.execute(&transaction, &[]).await?.iter().map(|row| (row.get(0), row.get(1))).collect(),
+            submissions: SUBMISSIONS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| SubmissionRow {
+                    submission_id: row.get(0),
+                    user_id: row.get(1),
+                    contest_id: row.get(2),
+                    problem_id: row.get(3),
+                    code: row.get(4),
+                    result: row.get(5),
+                    points: row.get(6),
+                    tests_done: row.get(7),
+                    submitted_at: row.get::<Option<DateTime<Utc>>>(8).map(|time| time.timestamp_micros()),
+                    submitted_by: row.get(9),
+                })
+                .collect(),
+            subtask_results: SUBTASK_RESULTS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| SubtaskResultRow {
+                    submission_id: row.get(0),
+                    subtask_id: row.get(1),
+                    points: row.get(2),
+                    result: row.get(3),
+                })
+                .collect(),
+            test_results: TEST_RESULTS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| TestResultRow {
+                    submission_id: row.get(0),
+                    test_id: row.get(1),
+                    result: row.get(2),
+                    time: row.get(3),
+                })
+                .collect(),
+            audit_log: AUDIT_LOG_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| AuditRow {
+                    audit_id: row.get(0),
+                    user_id: row.get(1),
+                    username: row.get(2),
+                    action: row.get(3),
+                    target: row.get(4),
+                    ip_address: row.get(5),
+                    before_summary: row.get(6),
+                    after_summary: row.get(7),
+                    created_at: row.get::<DateTime<Utc>>(8).timestamp_micros(),
+                })
+                .collect(),
+            clarifications: CLARIFICATIONS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| ClarificationRow {
+                    clarification_id: row.get(0),
+                    contest_id: row.get(1),
+                    problem_id: row.get(2),
+                    user_id: row.get(3),
+                    question: row.get(4),
+                    asked_at: row.get::<DateTime<Utc>>(5).timestamp_micros(),
+                    answer: row.get(6),
+                    answered_at: row.get::<Option<DateTime<Utc>>>(7).map(|time| time.timestamp_micros()),
+                    is_public: row.get(8),
+                })
+                .collect(),
+            announcements: ANNOUNCEMENTS_QUERY
+                .execute(&transaction, &[])
+                .await?
+                .iter()
+                .map(|row| AnnouncementRow {
+                    announcement_id: row.get(0),
+                    contest_id: row.get(1),
+                    problem_id: row.get(2),
+                    announcement_text: row.get(3),
+                    created_at: row.get::<DateTime<Utc>>(4).timestamp_micros(),
+                })
+                .collect(),
+            announcement_reads: ANNOUNCEMENT_READS_QUERY.execute(&transaction, &[]).await?.iter().map(|row| (row.get(0), row.get(1))).collect(),
+        };
+
+        transaction.commit().await?;
+        Ok(backup)
+    }
+
+    /// returns true if there are no users, contests or problems in the database
+    pub async fn is_empty(&self) -> Result<bool> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT NOT EXISTS (SELECT 1 FROM users) AND NOT EXISTS (SELECT 1 FROM contests) AND NOT EXISTS (SELECT 1 FROM problems)");
+
+        Ok(QUERY.execute(self, &[]).await?.first().ok_or_else(|| anyhow!("Could not retrieve the first row"))?.get(0))
+    }
+
+    /// writes everything from the backup into an empty database with the same schema version, ids are kept as they are
+    #[allow(clippy::too_many_lines, reason = "one query for every table")]
+    pub async fn restore_backup(&self, backup: &Backup) -> Result<()> {
+        static USERS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO users (user_id, username, password, is_admin, is_team) VALUES ($1, $2, $3, $4, $5)");
+        static USER_TEAMS_QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE users SET team_id = $2 WHERE user_id = $1");
+        static CONTESTS_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "INSERT INTO contests (contest_id, contest_name, start_time, end_time, contest_type, freeze_time, revealed_until, duration_minutes) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        );
+        static CONTEST_PARTICIPATIONS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO contest_participations (contest_id, user_id, started_at, clarifications_seen_at) VALUES ($1, $2, $3, $4)");
+        static PROBLEMS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO problems (problem_id, problem_name, problem_description, points, time_limit) VALUES ($1, $2, $3, $4, $5)");
+        static CONTEST_PROBLEMS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO contest_problems (contest_id, problem_id, position, label) VALUES ($1, $2, $3, $4)");
+        static USER_PROBLEM_SCORES_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO user_problem_scores (user_id, contest_id, problem_id, score, improved_at) VALUES ($1, $2, $3, $4, $5)");
+        static SUBTASKS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO subtasks (subtask_id, problem_id, subtask_score) VALUES ($1, $2, $3)");
+        static TESTS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO tests (test_id, problem_id, input_data, output_data) VALUES ($1, $2, $3, $4)");
+        static SUBTASK_TESTS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO subtask_tests (subtask_id, test_id) VALUES ($1, $2)");
+        static SUBMISSIONS_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "INSERT INTO submissions (submission_id, user_id, contest_id, problem_id, code, result, points, tests_done, submitted_at, submitted_by) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        );
+        static SUBTASK_RESULTS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO subtask_results (submission_id, subtask_id, points, result) VALUES ($1, $2, $3, $4)");
+        static TEST_RESULTS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO test_results (submission_id, test_id, result, time) VALUES ($1, $2, $3, $4)");
+        static AUDIT_LOG_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "INSERT INTO audit_log (audit_id, user_id, username, action, target, ip_address, before_summary, after_summary, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        );
+        static CLARIFICATIONS_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "INSERT INTO clarifications (clarification_id, contest_id, problem_id, user_id, question, asked_at, answer, answered_at, is_public) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        );
+        static ANNOUNCEMENTS_QUERY: DatabaseQuery =
+            DatabaseQuery::new("INSERT INTO announcements (announcement_id, contest_id, problem_id, announcement_text, created_at) VALUES ($1, $2, $3, $4, $5)");
+        static ANNOUNCEMENT_READS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO announcement_reads (announcement_id, user_id) VALUES ($1, $2)");
+        // postgres does not move the id sequences forward when ids are inserted explicitly, sqlite does
+        static RESET_SEQUENCES_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT
+                setval(pg_get_serial_sequence('users', 'user_id'), (SELECT COALESCE(MAX(user_id), 0) + 1 FROM users), false),
+                setval(pg_get_serial_sequence('contests', 'contest_id'), (SELECT COALESCE(MAX(contest_id), 0) + 1 FROM contests), false),
+                setval(pg_get_serial_sequence('problems', 'problem_id'), (SELECT COALESCE(MAX(problem_id), 0) + 1 FROM problems), false),
+                setval(pg_get_serial_sequence('subtasks', 'subtask_id'), (SELECT COALESCE(MAX(subtask_id), 0) + 1 FROM subtasks), false),
+                setval(pg_get_serial_sequence('tests', 'test_id'), (SELECT COALESCE(MAX(test_id), 0) + 1 FROM tests), false),
+                setval(pg_get_serial_sequence('submissions', 'submission_id'), (SELECT COALESCE(MAX(submission_id), 0) + 1 FROM submissions), false),
+                setval(pg_get_serial_sequence('audit_log', 'audit_id'), (SELECT COALESCE(MAX(audit_id), 0) + 1 FROM audit_log), false),
+                setval(pg_get_serial_sequence('clarifications', 'clarification_id'), (SELECT COALESCE(MAX(clarification_id), 0) + 1 FROM clarifications), false),
+                setval(pg_get_serial_sequence('announcements', 'announcement_id'), (SELECT COALESCE(MAX(announcement_id), 0) + 1 FROM announcements), false)",
+        );
+
+        let schema_version = self.get_schema_version().await?;
+        if backup.schema_version != schema_version {
+            bail!("The backup has schema version {}, but the database has schema version {schema_version}", backup.schema_version);
+        }
+
+        let transaction = self.begin_transaction().await?;
+
+        if !transaction.is_empty().await? {
+            bail!("The database is not empty, a backup can only be restored into an empty database");
+        }
+
+        for user in &backup.users {
+            USERS_QUERY
+                .execute(&transaction, &[&user.user_id, &user.username, &user.password, &user.is_admin, &user.is_team])
+                .await?;
+        }
+        // a team can have a larger id than its members, so they are only assigned once all users exist
+        for user in &backup.users {
+            if let Some(team_id) = user.team_id {
+                USER_TEAMS_QUERY.execute(&transaction, &[&user.user_id, &team_id]).await?;
+            }
+        }
+        for contest in &backup.contests {
+            let start_time = contest
+                .start_time
+                .map(|time| DateTime::<Utc>::from_timestamp_micros(time).ok_or_else(|| anyhow!("Invalid contest start time")))
+                .transpose()?;
+            let end_time = contest
+                .end_time
+                .map(|time| DateTime::<Utc>::from_timestamp_micros(time).ok_or_else(|| anyhow!("Invalid contest end time")))
+                .transpose()?;
+            let freeze_time = contest
+                .freeze_time
+                .map(|time| DateTime::<Utc>::from_timestamp_micros(time).ok_or_else(|| anyhow!("Invalid contest freeze time")))
+                .transpose()?;
+            CONTESTS_QUERY
+                .execute(
+                    &transaction,
+                    &[
+                        &contest.contest_id,
+                        &contest.contest_name,
+                        &start_time,
+                        &end_time,
+                        &contest.contest_type,
+                        &freeze_time,
+                        &contest.revealed_until,
+                        &contest.duration_minutes,
+                    ],
+                )
+                .await?;
+        }
+        for (contest_id, user_id, started_at, clarifications_seen_at) in &backup.contest_participations {
+            let started_at = started_at
+                .map(|time| DateTime::<Utc>::from_timestamp_micros(time).ok_or_else(|| anyhow!("Invalid participation start time")))
+                .transpose()?;
+            let clarifications_seen_at = clarifications_seen_at
+                .map(|time| DateTime::<Utc>::from_timestamp_micros(time).ok_or_else(|| anyhow!("Invalid clarification seen time")))
+                .transpose()?;
+            CONTEST_PARTICIPATIONS_QUERY.execute(&transaction, &[contest_id, user_id, &started_at, &clarifications_seen_at]).await?;
+        }
+        for problem in &backup.problems {
+            PROBLEMS_QUERY
+                .execute(
+                    &transaction,
+                    &[&problem.problem_id, &problem.problem_name, &problem.problem_description, &problem.points, &problem.time_limit],
+                )
+                .await?;
+        }
+        for (contest_id, problem_id, position, label) in &backup.contest_problems {
+            CONTEST_PROBLEMS_QUERY.execute(&transaction, &[contest_id, problem_id, position, label]).await?;
+        }
+        for (user_id, contest_id, problem_id, score, improved_at) in &backup.user_problem_scores {
+            let improved_at = improved_at
+                .map(|time| DateTime::<Utc>::from_timestamp_micros(time).ok_or_else(|| anyhow!("Invalid score improvement time")))
+                .transpose()?;
+            USER_PROBLEM_SCORES_QUERY.execute(&transaction, &[user_id, contest_id, problem_id, score, &improved_at]).await?;
+        }
+        for subtask in &backup.subtasks {
+            SUBTASKS_QUERY.execute(&transaction, &[&subtask.subtask_id, &subtask.problem_id, &subtask.subtask_score]).await?;
+        }
+        for test in &backup.tests {
+            TESTS_QUERY.execute(&transaction, &[&test.test_id, &test.problem_id, &test.input_data, &test.output_data]).await?;
+        }
+        for (subtask_id, test_id) in &backup.subtask_tests {
+            SUBTASK_TESTS_QUERY.execute(&transaction, &[subtask_id, test_id]).await?;
+        }
+        for submission in &backup.submissions {
+            let submitted_at = submission
+                .submitted_at
+                .map(|time| DateTime::<Utc>::from_timestamp_micros(time).ok_or_else(|| anyhow!("Invalid submission time")))
+                .transpose()?;
+            SUBMISSIONS_QUERY
+                .execute(
+                    &transaction,
+                    &[
+                        &submission.submission_id,
+                        &submission.user_id,
+                        &submission.contest_id,
+                        &submission.problem_id,
+                        &submission.code,
+                        &submission.result,
+                        &submission.points,
+                        &submission.tests_done,
+                        &submitted_at,
+                        &submission.submitted_by,
+                    ],
+                )
+                .await?;
+        }
+        for result in &backup.subtask_results {
+            SUBTASK_RESULTS_QUERY
+                .execute(&transaction, &[&result.submission_id, &result.subtask_id, &result.points, &result.result])
+                .await?;
+        }
+        for result in &backup.test_results {
+            TEST_RESULTS_QUERY
+                .execute(&transaction, &[&result.submission_id, &result.test_id, &result.result, &result.time])
+                .await?;
+        }
+        for entry in &backup.audit_log {
+            let created_at = DateTime::<Utc>::from_timestamp_micros(entry.created_at).ok_or_else(|| anyhow!("Invalid timestamp in the audit log"))?;
+            AUDIT_LOG_QUERY
+                .execute(
+                    &transaction,
+                    &[
+                        &entry.audit_id,
+                        &entry.user_id,
+                        &entry.username,
+                        &entry.action,
+                        &entry.target,
+                        &entry.ip_address,
+                        &entry.before_summary,
+                        &entry.after_summary,
+                        &created_at,
+                    ],
+                )
+                .await?;
+        }
+
+        for clarification in &backup.clarifications {
+            let asked_at = DateTime::<Utc>::from_timestamp_micros(clarification.asked_at).ok_or_else(|| anyhow!("Invalid clarification time"))?;
+            let answered_at = clarification
+                .answered_at
+                .map(|time| DateTime::<Utc>::from_timestamp_micros(time).ok_or_else(|| anyhow!("Invalid clarification answer time")))
+                .transpose()?;
+            CLARIFICATIONS_QUERY
+                .execute(
+                    &transaction,
+                    &[
+                        &clarification.clarification_id,
+                        &clarification.contest_id,
+                        &clarification.problem_id,
+                        &clarification.user_id,
+                        &clarification.question,
+                        &asked_at,
+                        &clarification.answer,
+                        &answered_at,
+                        &clarification.is_public,
+                    ],
+                )
+                .await?;
+        }
+
+        for announcement in &backup.announcements {
+            let created_at = DateTime::<Utc>::from_timestamp_micros(announcement.created_at).ok_or_else(|| anyhow!("Invalid announcement time"))?;
+            ANNOUNCEMENTS_QUERY
+                .execute(
+                    &transaction,
+                    &[
+                        &announcement.announcement_id,
+                        &announcement.contest_id,
+                        &announcement.problem_id,
+                        &announcement.announcement_text,
+                        &created_at,
+                    ],
+                )
+                .await?;
+        }
+        for (announcement_id, user_id) in &backup.announcement_reads {
+            ANNOUNCEMENT_READS_QUERY.execute(&transaction, &[announcement_id, user_id]).await?;
+        }
+
+        if self.backend() == Backend::Postgres {
+            RESET_SEQUENCES_QUERY.execute(&transaction, &[]).await?;
+        }
+
+        // missing users and problems might have been cached before
+        transaction.invalidate_everything()?;
+        transaction.commit().await
+    }
+}
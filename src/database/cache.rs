@@ -0,0 +1,209 @@
+use crate::database::contest::{ContestId, ContestTimes};
+use crate::database::problem::ProblemId;
+use crate::database::user::UserId;
+use crate::database::Database;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// how often a cache was used, shown on the judge status page
+#[derive(serde::Serialize)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(serde::Serialize)]
+pub struct DatabaseStatus {
+    pub query_count: u64,
+    pub caches: Vec<CacheStats>,
+}
+
+/// cached values of one kind, like the names of all problems
+pub struct CacheMap<K, V> {
+    name: &'static str,
+    values: Mutex<HashMap<K, V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<K: Eq + Hash, V: Clone> CacheMap<K, V> {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            values: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    fn get(&self, key: &K) -> Result<Option<V>> {
+        let value = self.values.lock().map_err(|e| anyhow!("{e}"))?.get(key).cloned();
+        if value.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(value)
+    }
+
+    /// the value is only stored if nothing was invalidated since it was read from the database, otherwise it might be outdated
+    fn insert(&self, key: K, value: V, generation: &AtomicU64, read_generation: u64) -> Result<()> {
+        // the generation is checked while holding the lock, so an invalidation cannot happen in between
+        let mut values = self.values.lock().map_err(|e| anyhow!("{e}"))?;
+        if generation.load(Ordering::SeqCst) == read_generation {
+            values.insert(key, value);
+        }
+        drop(values);
+        Ok(())
+    }
+
+    fn remove(&self, key: &K) -> Result<()> {
+        self.values.lock().map_err(|e| anyhow!("{e}"))?.remove(key);
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.values.lock().map_err(|e| anyhow!("{e}"))?.clear();
+        Ok(())
+    }
+
+    fn get_stats(&self) -> Result<CacheStats> {
+        Ok(CacheStats {
+            name: self.name,
+            entries: self.values.lock().map_err(|e| anyhow!("{e}"))?.len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        })
+    }
+}
+
+/// values that are read on almost every request, but rarely change.
+/// every method that changes one of them has to invalidate it.
+pub struct DatabaseCache {
+    pub problem_names: CacheMap<ProblemId, String>,
+    pub problem_points: CacheMap<ProblemId, i32>,
+    pub problem_time_limits: CacheMap<ProblemId, i32>,
+    pub contest_names: CacheMap<ContestId, String>,
+    pub contest_times: CacheMap<ContestId, ContestTimes>,
+    pub usernames: CacheMap<UserId, Option<String>>,
+    pub user_admins: CacheMap<UserId, bool>,
+    // the team each user acts as, None for users without a team
+    pub user_teams: CacheMap<UserId, Option<UserId>>,
+    // increased on every invalidation
+    generation: AtomicU64,
+}
+
+impl DatabaseCache {
+    pub fn new() -> Self {
+        Self {
+            problem_names: CacheMap::new("problem names"),
+            problem_points: CacheMap::new("problem points"),
+            problem_time_limits: CacheMap::new("problem time limits"),
+            contest_names: CacheMap::new("contest names"),
+            contest_times: CacheMap::new("contest times"),
+            usernames: CacheMap::new("usernames"),
+            user_admins: CacheMap::new("user admins"),
+            user_teams: CacheMap::new("user teams"),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.problem_names.clear()?;
+        self.problem_points.clear()?;
+        self.problem_time_limits.clear()?;
+        self.contest_names.clear()?;
+        self.contest_times.clear()?;
+        self.usernames.clear()?;
+        self.user_admins.clear()?;
+        self.user_teams.clear()
+    }
+
+    fn get_stats(&self) -> Result<Vec<CacheStats>> {
+        Ok(vec![
+            self.problem_names.get_stats()?,
+            self.problem_points.get_stats()?,
+            self.problem_time_limits.get_stats()?,
+            self.contest_names.get_stats()?,
+            self.contest_times.get_stats()?,
+            self.usernames.get_stats()?,
+            self.user_admins.get_stats()?,
+            self.user_teams.get_stats()?,
+        ])
+    }
+}
+
+impl Database {
+    /// returns the cached value or loads it, the cache is not used inside of transactions,
+    /// because they can see changes that are never committed
+    pub async fn cached<K: Eq + Hash, V: Clone, F: Future<Output = Result<V>>>(&self, select: fn(&DatabaseCache) -> &CacheMap<K, V>, key: K, load: F) -> Result<V> {
+        if self.transaction.is_some() {
+            return load.await;
+        }
+
+        let map = select(&self.cache);
+        let generation = self.cache.generation.load(Ordering::SeqCst);
+        if let Some(value) = map.get(&key)? {
+            return Ok(value);
+        }
+
+        let value = load.await?;
+        map.insert(key, value.clone(), &self.cache.generation, generation)?;
+        Ok(value)
+    }
+
+    /// has to be called after the value was changed in the database, inside of a transaction it waits for the commit
+    pub fn invalidate<K: Eq + Hash + Clone + Send + 'static, V: Clone + 'static>(&self, select: fn(&DatabaseCache) -> &CacheMap<K, V>, key: &K) -> Result<()> {
+        if let Some(transaction) = &self.transaction {
+            let key = key.clone();
+            transaction
+                .invalidations
+                .lock()
+                .map_err(|e| anyhow!("{e}"))?
+                .push(Box::new(move |database| database.invalidate(select, &key)));
+            return Ok(());
+        }
+
+        self.cache.generation.fetch_add(1, Ordering::SeqCst);
+        select(&self.cache).remove(key)
+    }
+
+    /// has to be called after values were changed that cannot be listed by their keys
+    pub fn invalidate_all<K: Eq + Hash + 'static, V: Clone + 'static>(&self, select: fn(&DatabaseCache) -> &CacheMap<K, V>) -> Result<()> {
+        if let Some(transaction) = &self.transaction {
+            transaction
+                .invalidations
+                .lock()
+                .map_err(|e| anyhow!("{e}"))?
+                .push(Box::new(move |database| database.invalidate_all(select)));
+            return Ok(());
+        }
+
+        self.cache.generation.fetch_add(1, Ordering::SeqCst);
+        select(&self.cache).clear()
+    }
+
+    /// has to be called after changes that can touch any cached value, like restoring a backup
+    pub fn invalidate_everything(&self) -> Result<()> {
+        if let Some(transaction) = &self.transaction {
+            transaction.invalidations.lock().map_err(|e| anyhow!("{e}"))?.push(Box::new(Self::invalidate_everything));
+            return Ok(());
+        }
+
+        self.cache.clear()
+    }
+
+    pub fn get_database_status(&self) -> Result<DatabaseStatus> {
+        Ok(DatabaseStatus {
+            query_count: self.get_query_count(),
+            caches: self.cache.get_stats()?,
+        })
+    }
+}
@@ -0,0 +1,97 @@
+use crate::database::contest::ContestId;
+use crate::database::problem::{get_problem_title, ProblemId};
+use crate::database::user::UserId;
+use crate::database::{Database, DatabaseQuery};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+pub type ClarificationId = i32;
+
+/// a question of a contestant, unanswered while the answer is None
+pub struct ClarificationEntry {
+    pub clarification_id: ClarificationId,
+    pub username: String,
+    // the label and name of the problem, empty for questions about the whole contest
+    pub problem: String,
+    pub question: String,
+    pub asked_at: DateTime<Utc>,
+    pub answer: Option<String>,
+    pub answered_at: Option<DateTime<Utc>>,
+    pub is_public: bool,
+}
+
+impl Database {
+    pub async fn add_clarification(&self, user_id: UserId, contest_id: ContestId, problem_id: Option<ProblemId>, question: &str) -> Result<ClarificationId> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO clarifications (contest_id, problem_id, user_id, question, asked_at) VALUES ($1, $2, $3, $4, $5) RETURNING clarification_id");
+
+        Ok(QUERY
+            .execute(self, &[&contest_id, &problem_id, &user_id, &question, &Utc::now()])
+            .await?
+            .first()
+            .ok_or_else(|| anyhow!("No clarification id returned"))?
+            .get(0))
+    }
+
+    /// the newest clarifications first, a viewer sees their own questions and the public answers, None sees everything
+    pub async fn get_clarifications(&self, contest_id: ContestId, viewer: Option<UserId>) -> Result<Vec<ClarificationEntry>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT clarifications.clarification_id, users.username, contest_problems.label, problems.problem_name, clarifications.question, clarifications.asked_at,
+                clarifications.answer, clarifications.answered_at, clarifications.is_public
+            FROM clarifications
+            JOIN users ON users.user_id = clarifications.user_id
+            LEFT JOIN problems ON problems.problem_id = clarifications.problem_id
+            LEFT JOIN contest_problems ON contest_problems.contest_id = clarifications.contest_id AND contest_problems.problem_id = clarifications.problem_id
+            WHERE clarifications.contest_id = $1 AND ($2 = 0 OR clarifications.user_id = $2 OR (clarifications.is_public AND clarifications.answer IS NOT NULL))
+            ORDER BY clarifications.clarification_id DESC",
+        );
+
+        Ok(QUERY
+            .execute(self, &[&contest_id, &viewer.unwrap_or(0)])
+            .await?
+            .iter()
+            .map(|row| ClarificationEntry {
+                clarification_id: row.get(0),
+                username: row.get(1),
+                problem: get_problem_title(row.get(2), row.get(3)),
+                question: row.get(4),
+                asked_at: row.get(5),
+                answer: row.get(6),
+                answered_at: row.get(7),
+                is_public: row.get(8),
+            })
+            .collect())
+    }
+
+    /// a clarification is only answered once, returns false if it does not exist in the contest or already has an answer
+    pub async fn answer_clarification(&self, contest_id: ContestId, clarification_id: ClarificationId, answer: &str, is_public: bool) -> Result<bool> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "UPDATE clarifications SET answer = $3, answered_at = $4, is_public = $5
+            WHERE contest_id = $1 AND clarification_id = $2 AND answer IS NULL RETURNING clarification_id",
+        );
+
+        Ok(!QUERY.execute(self, &[&contest_id, &clarification_id, &answer, &Utc::now(), &is_public]).await?.is_empty())
+    }
+
+    /// how many answers the user has not seen yet in each of their contests, contests without new answers are missing
+    pub async fn get_new_answer_counts(&self, user_id: UserId) -> Result<HashMap<ContestId, i64>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT contest_participations.contest_id, COUNT(*)
+            FROM contest_participations
+            JOIN clarifications ON clarifications.contest_id = contest_participations.contest_id
+            WHERE contest_participations.user_id = $1 AND clarifications.answer IS NOT NULL
+                AND (clarifications.user_id = $1 OR clarifications.is_public)
+                AND (contest_participations.clarifications_seen_at IS NULL OR clarifications.answered_at > contest_participations.clarifications_seen_at)
+            GROUP BY contest_participations.contest_id",
+        );
+
+        Ok(QUERY.execute(self, &[&user_id]).await?.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    pub async fn mark_clarifications_seen(&self, user_id: UserId, contest_id: ContestId, seen_at: DateTime<Utc>) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE contest_participations SET clarifications_seen_at = $3 WHERE user_id = $1 AND contest_id = $2");
+
+        QUERY.execute(self, &[&user_id, &contest_id, &seen_at]).await?;
+        Ok(())
+    }
+}
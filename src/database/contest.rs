@@ -1,52 +1,121 @@
+use crate::database::problem::ProblemId;
+use crate::database::submission::SubmissionId;
 use crate::database::user::UserId;
 use crate::database::{Database, DatabaseQuery};
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Utc};
 
 pub type ContestId = i32;
 
-impl Database {
-    pub async fn init_contests(&self) -> Result<()> {
-        // add the contests table
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS contests (
-                    contest_id SERIAL PRIMARY KEY,
-                    contest_name VARCHAR(100) UNIQUE NOT NULL
-                );",
-                &[],
-            )
-            .await?;
+/// a contest with its name and its problems as (problem id, label, problem name, score of the user, max points)
+pub type ContestOverview = (ContestId, String, Vec<(ProblemId, String, String, i32, i32)>);
+
+/// when submissions to a contest are accepted, a missing time means there is no limit on that side
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ContestTimes {
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    /// results submitted from this time on are hidden from other participants until they are revealed
+    pub freeze_time: Option<DateTime<Utc>>,
+    /// in windowed contests every participant starts within the start and end time and then has this many minutes
+    pub duration_minutes: Option<i32>,
+}
 
-        // add the table of contest participations
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS contest_participations (
-                    contest_id INT REFERENCES contests(contest_id),
-                    user_id INT REFERENCES users(user_id),
-                    PRIMARY KEY (contest_id, user_id)
-                );",
-                &[],
-            )
-            .await?;
+impl ContestTimes {
+    pub fn has_started(&self, now: DateTime<Utc>) -> bool {
+        self.start_time.is_none_or(|start_time| now >= start_time)
+    }
 
-        Ok(())
+    pub fn has_ended(&self, now: DateTime<Utc>) -> bool {
+        self.end_time.is_some_and(|end_time| now >= end_time)
     }
 
-    pub async fn is_contest_id_valid(&self, contest_id: ContestId) -> bool {
-        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT contest_id FROM contests WHERE contest_id = $1");
+    pub fn is_running(&self, now: DateTime<Utc>) -> bool {
+        self.has_started(now) && !self.has_ended(now)
+    }
+
+    pub fn is_frozen(&self, now: DateTime<Utc>) -> bool {
+        self.freeze_time.is_some_and(|freeze_time| now >= freeze_time)
+    }
+
+    pub const fn is_windowed(&self) -> bool {
+        self.duration_minutes.is_some()
+    }
+
+    /// the times of a participant that started at the given time, None if the contest is windowed and the participant has not started yet.
+    /// the personal end is never after the end of the window
+    pub fn for_participant(&self, started_at: Option<DateTime<Utc>>) -> Option<Self> {
+        let Some(duration_minutes) = self.duration_minutes else {
+            return Some(*self);
+        };
+        let started_at = started_at?;
+        let personal_end = started_at + Duration::try_minutes(i64::from(duration_minutes)).unwrap_or(Duration::zero());
+        Some(Self {
+            start_time: Some(started_at),
+            end_time: Some(self.end_time.map_or(personal_end, |end_time| end_time.min(personal_end))),
+            ..*self
+        })
+    }
+
+    /// moves the end back by the given number of minutes, in windowed contests every participant also gets that much more time
+    pub fn extended_by(&self, minutes: i32) -> Result<Self> {
+        if self.end_time.is_none() && !self.is_windowed() {
+            bail!("The contest has no end time");
+        }
+        let extension = Duration::try_minutes(i64::from(minutes)).ok_or_else(|| anyhow!("Invalid number of minutes"))?;
+        let duration_minutes = self
+            .duration_minutes
+            .map(|duration_minutes| duration_minutes.checked_add(minutes).ok_or_else(|| anyhow!("The contest duration is too long")))
+            .transpose()?;
+        Ok(Self {
+            end_time: self.end_time.map(|end_time| end_time + extension),
+            duration_minutes,
+            ..*self
+        })
+    }
+}
+
+/// how the scoreboard of a contest is computed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ContestType {
+    /// the sum of the best subtask points
+    Ioi,
+    /// solved problems and penalty time
+    Icpc,
+}
+
+// make sure that contest types are stored in the database as integers
+pub const fn contest_type_to_i32(contest_type: ContestType) -> i32 {
+    match contest_type {
+        ContestType::Ioi => 1,
+        ContestType::Icpc => 2,
+    }
+}
+
+pub const fn i32_to_contest_type(contest_type: i32) -> ContestType {
+    match contest_type {
+        2 => ContestType::Icpc,
+        _ => ContestType::Ioi, // 1 or anything else is an ioi contest
+    }
+}
 
-        QUERY.execute(self, &[&contest_id]).await.ok().is_some_and(|rows| !rows.is_empty())
+impl Database {
+    pub async fn is_contest_id_valid(&self, contest_id: ContestId) -> bool {
+        self.get_contest_name(contest_id).await.is_ok()
     }
 
     pub async fn get_contest_name(&self, contest_id: ContestId) -> Result<String> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT contest_name FROM contests WHERE contest_id = $1");
 
-        Ok(QUERY
-            .execute(self, &[&contest_id])
-            .await?
-            .first()
-            .ok_or_else(|| anyhow::anyhow!("No contest with id {}", contest_id))?
-            .get(0))
+        self.cached(|cache| &cache.contest_names, contest_id, async {
+            Ok(QUERY
+                .execute(self, &[&contest_id])
+                .await?
+                .first()
+                .ok_or_else(|| anyhow::anyhow!("No contest with id {}", contest_id))?
+                .get(0))
+        })
+        .await
     }
 
     pub async fn get_all_contests(&self) -> Result<Vec<ContestId>> {
@@ -65,23 +134,125 @@ impl Database {
         Ok(QUERY.execute(self, &[&user_id]).await?.iter().map(|row| row.get(0)).collect())
     }
 
+    /// all contests the user can see with their problems and the user's scores in one query
+    pub async fn get_contest_overviews_for_user(&self, user_id: UserId) -> Result<Vec<ContestOverview>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT contests.contest_id, contests.contest_name, problems.problem_id, problems.problem_name, problems.points, COALESCE(user_problem_scores.score, 0), contest_problems.label
+            FROM contests
+            LEFT JOIN contest_problems ON contest_problems.contest_id = contests.contest_id
+            LEFT JOIN problems ON problems.problem_id = contest_problems.problem_id
+            LEFT JOIN user_problem_scores ON user_problem_scores.problem_id = problems.problem_id AND user_problem_scores.contest_id = contests.contest_id AND user_problem_scores.user_id = $1
+            WHERE EXISTS (SELECT 1 FROM users WHERE users.user_id = $1 AND users.is_admin)
+                OR contests.contest_id IN (SELECT contest_id FROM contest_participations WHERE user_id = $1)
+            ORDER BY contests.contest_id, contest_problems.position, problems.problem_id",
+        );
+
+        let mut contests: Vec<ContestOverview> = Vec::new();
+        for row in QUERY.execute(self, &[&user_id]).await? {
+            let contest_id: ContestId = row.get(0);
+            if contests.last().map(|contest| contest.0) != Some(contest_id) {
+                contests.push((contest_id, row.get(1), Vec::new()));
+            }
+
+            // contests without problems have a single row with no problem
+            if let (Some(problem_id), Some(contest)) = (row.get::<Option<ProblemId>>(2), contests.last_mut()) {
+                contest.2.push((problem_id, row.get(6), row.get(3), row.get(5), row.get(4)));
+            }
+        }
+
+        Ok(contests)
+    }
+
+    /// all users that can participate with whether they participate in the contest, ordered by id.
+    /// team members are left out, since their team participates instead
+    pub async fn get_all_users_with_participation(&self, contest_id: ContestId) -> Result<Vec<(UserId, String, bool)>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT users.user_id, users.username, contest_participations.user_id IS NOT NULL
+            FROM users
+            LEFT JOIN contest_participations ON contest_participations.user_id = users.user_id AND contest_participations.contest_id = $1
+            WHERE users.team_id IS NULL
+            ORDER BY users.user_id",
+        );
+
+        Ok(QUERY.execute(self, &[&contest_id]).await?.iter().map(|row| (row.get(0), row.get(1), row.get(2))).collect())
+    }
+
     pub async fn add_contest(&self, contest_name: &str) -> Result<ContestId> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO contests (contest_name) VALUES ($1) RETURNING contest_id");
 
         Ok(QUERY.execute(self, &[&contest_name]).await?.first().ok_or_else(|| anyhow!("Could not retrieve the first row"))?.get(0))
     }
 
-    pub async fn remove_contest(&self, contest_id: ContestId) -> Result<()> {
-        static QUERY: DatabaseQuery = DatabaseQuery::new("DELETE FROM contests WHERE contest_id = $1");
+    pub async fn set_contest_name(&self, contest_id: ContestId, contest_name: &str) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE contests SET contest_name = $2 WHERE contest_id = $1");
 
-        self.remove_all_participations_for_contest(contest_id).await?;
-        self.remove_all_problems_from_contest(contest_id).await?;
+        QUERY.execute(self, &[&contest_id, &contest_name]).await?;
+        self.invalidate(|cache| &cache.contest_names, &contest_id)
+    }
 
-        QUERY.execute(self, &[&contest_id]).await?;
+    pub async fn get_contest_times(&self, contest_id: ContestId) -> Result<ContestTimes> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT start_time, end_time, freeze_time, duration_minutes FROM contests WHERE contest_id = $1");
+
+        self.cached(|cache| &cache.contest_times, contest_id, async {
+            let rows = QUERY.execute(self, &[&contest_id]).await?;
+            let row = rows.first().ok_or_else(|| anyhow!("No contest with id {}", contest_id))?;
+            Ok(ContestTimes {
+                start_time: row.get(0),
+                end_time: row.get(1),
+                freeze_time: row.get(2),
+                duration_minutes: row.get(3),
+            })
+        })
+        .await
+    }
+
+    pub async fn set_contest_times(&self, contest_id: ContestId, times: ContestTimes) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE contests SET start_time = $2, end_time = $3, freeze_time = $4, duration_minutes = $5 WHERE contest_id = $1");
+
+        QUERY
+            .execute(self, &[&contest_id, &times.start_time, &times.end_time, &times.freeze_time, &times.duration_minutes])
+            .await?;
+        self.invalidate(|cache| &cache.contest_times, &contest_id)
+    }
 
+    pub async fn get_contest_type(&self, contest_id: ContestId) -> Result<ContestType> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT contest_type FROM contests WHERE contest_id = $1");
+
+        let rows = QUERY.execute(self, &[&contest_id]).await?;
+        Ok(i32_to_contest_type(rows.first().ok_or_else(|| anyhow!("No contest with id {}", contest_id))?.get(0)))
+    }
+
+    pub async fn set_contest_type(&self, contest_id: ContestId, contest_type: ContestType) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE contests SET contest_type = $2 WHERE contest_id = $1");
+
+        QUERY.execute(self, &[&contest_id, &contest_type_to_i32(contest_type)]).await?;
         Ok(())
     }
 
+    /// the last submission that is shown on the scoreboard after the freeze, None if nothing was revealed yet
+    pub async fn get_revealed_until(&self, contest_id: ContestId) -> Result<Option<SubmissionId>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT revealed_until FROM contests WHERE contest_id = $1");
+
+        let rows = QUERY.execute(self, &[&contest_id]).await?;
+        Ok(rows.first().ok_or_else(|| anyhow!("No contest with id {}", contest_id))?.get(0))
+    }
+
+    pub async fn set_revealed_until(&self, contest_id: ContestId, revealed_until: Option<SubmissionId>) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE contests SET revealed_until = $2 WHERE contest_id = $1");
+
+        QUERY.execute(self, &[&contest_id, &revealed_until]).await?;
+        Ok(())
+    }
+
+    /// deletes the contest and its participations, the problems stay
+    pub async fn remove_contest(&self, contest_id: ContestId) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("DELETE FROM contests WHERE contest_id = $1");
+
+        QUERY.execute(self, &[&contest_id]).await?;
+        self.invalidate(|cache| &cache.contest_times, &contest_id)?;
+        self.invalidate(|cache| &cache.contest_names, &contest_id)
+    }
+
     pub async fn remove_all_participations_for_contest(&self, contest_id: ContestId) -> Result<()> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("DELETE FROM contest_participations WHERE contest_id = $1");
 
@@ -107,12 +278,16 @@ impl Database {
         self.add_contest(contest_name).await
     }
 
+    /// team members cannot participate on their own, they act as their team in every contest
     pub async fn add_user_to_contest(&self, user_id: UserId, contest_id: ContestId) -> Result<()> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO contest_participations (contest_id, user_id) VALUES ($1, $2)");
 
         if self.is_user_in_contest(user_id, contest_id).await? {
             return Ok(());
         }
+        if self.get_user_team(user_id).await?.is_some() {
+            bail!("User {user_id} is a team member and participates through their team");
+        }
 
         QUERY.execute(self, &[&contest_id, &user_id]).await?;
         Ok(())
@@ -136,6 +311,37 @@ impl Database {
         Ok(())
     }
 
+    /// when the participant started a windowed contest, None if they did not start yet or do not participate
+    pub async fn get_participation_start(&self, user_id: UserId, contest_id: ContestId) -> Result<Option<DateTime<Utc>>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT started_at FROM contest_participations WHERE user_id = $1 AND contest_id = $2");
+
+        Ok(QUERY.execute(self, &[&user_id, &contest_id]).await?.first().and_then(|row| row.get(0)))
+    }
+
+    /// the start of a participant is only set once
+    pub async fn start_participation(&self, user_id: UserId, contest_id: ContestId, started_at: DateTime<Utc>) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE contest_participations SET started_at = $3 WHERE user_id = $1 AND contest_id = $2 AND started_at IS NULL");
+
+        QUERY.execute(self, &[&user_id, &contest_id, &started_at]).await?;
+        Ok(())
+    }
+
+    /// the times of the user in the contest, admins are not limited by a window.
+    /// None if the user does not participate or has not started the windowed contest yet
+    pub async fn get_user_contest_times(&self, user_id: UserId, contest_id: ContestId) -> Result<Option<ContestTimes>> {
+        let times = self.get_contest_times(contest_id).await?;
+        if self.is_user_admin(user_id).await? {
+            return Ok(Some(times));
+        }
+        if !self.is_user_in_contest(user_id, contest_id).await? {
+            return Ok(None);
+        }
+        if !times.is_windowed() {
+            return Ok(Some(times));
+        }
+        Ok(times.for_participant(self.get_participation_start(user_id, contest_id).await?))
+    }
+
     pub async fn is_user_in_contest(&self, user_id: UserId, contest_id: ContestId) -> Result<bool> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT user_id FROM contest_participations WHERE user_id = $1 AND contest_id = $2");
 
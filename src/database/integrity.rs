@@ -0,0 +1,128 @@
+use crate::database::submission::{testing_result_to_i32, TestingResult};
+use crate::database::{Database, DatabaseQuery};
+use anyhow::Result;
+
+/// a stored value that does not match the value it is derived from
+pub struct IntegrityIssue {
+    pub kind: &'static str,
+    pub target: String,
+    pub stored: i64,
+    pub expected: i64,
+}
+
+// submissions that are still being judged or were never judged have no aggregates to check
+const UNFINISHED_RESULTS: [TestingResult; 4] = [TestingResult::InQueue, TestingResult::Compiling, TestingResult::Testing, TestingResult::CompilationError];
+
+impl Database {
+    /// finds all derived values that are out of date, nothing is changed
+    pub async fn check_integrity(&self) -> Result<Vec<IntegrityIssue>> {
+        static PROBLEM_POINTS_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT problem_id, points, expected FROM (
+                SELECT problem_id, points, (SELECT COALESCE(SUM(subtask_score), 0) FROM subtasks WHERE subtasks.problem_id = problems.problem_id) AS expected FROM problems
+            ) AS computed WHERE points <> expected ORDER BY problem_id",
+        );
+        static SUBMISSION_POINTS_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT submission_id, points, expected FROM (
+                SELECT submission_id, points, (SELECT COALESCE(SUM(points), 0) FROM subtask_results WHERE subtask_results.submission_id = submissions.submission_id) AS expected
+                FROM submissions WHERE result NOT IN ($1, $2, $3, $4)
+            ) AS computed WHERE points IS NULL OR points <> expected ORDER BY submission_id",
+        );
+        static SUBMISSION_TESTS_DONE_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT submission_id, tests_done, expected FROM (
+                SELECT submission_id, tests_done, (SELECT COUNT(*) FROM test_results WHERE test_results.submission_id = submissions.submission_id) AS expected
+                FROM submissions WHERE result NOT IN ($1, $2, $3, $4)
+            ) AS computed WHERE tests_done <> expected ORDER BY submission_id",
+        );
+        // the same score as in update_user_score_for_problem, a missing score counts as 0
+        static USER_SCORES_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT user_id, contest_id, problem_id, stored, expected FROM (
+                SELECT triples.user_id, triples.contest_id, triples.problem_id, COALESCE(scores.score, 0) AS stored, COALESCE(best.score, 0) AS expected
+                FROM (
+                    SELECT user_id, contest_id, problem_id FROM submissions WHERE user_id IS NOT NULL AND contest_id IS NOT NULL AND problem_id IS NOT NULL
+                    UNION SELECT user_id, contest_id, problem_id FROM user_problem_scores
+                ) AS triples
+                LEFT JOIN user_problem_scores AS scores ON scores.user_id = triples.user_id AND scores.contest_id = triples.contest_id AND scores.problem_id = triples.problem_id
+                LEFT JOIN (
+                    SELECT user_id, contest_id, problem_id, SUM(best_points) AS score FROM (
+                        SELECT submissions.user_id, submissions.contest_id, submissions.problem_id, MAX(subtask_results.points) AS best_points
+                        FROM subtask_results
+                        JOIN submissions ON submissions.submission_id = subtask_results.submission_id
+                        JOIN subtasks ON subtasks.subtask_id = subtask_results.subtask_id AND subtasks.problem_id = submissions.problem_id
+                        GROUP BY submissions.user_id, submissions.contest_id, submissions.problem_id, subtask_results.subtask_id
+                    ) AS best_subtask_points GROUP BY user_id, contest_id, problem_id
+                ) AS best ON best.user_id = triples.user_id AND best.contest_id = triples.contest_id AND best.problem_id = triples.problem_id
+            ) AS computed WHERE stored <> expected ORDER BY user_id, contest_id, problem_id",
+        );
+
+        let [in_queue, compiling, testing, compilation_error] = UNFINISHED_RESULTS.map(testing_result_to_i32);
+        let mut issues = Vec::new();
+
+        for row in PROBLEM_POINTS_QUERY.execute(self, &[]).await? {
+            issues.push(IntegrityIssue {
+                kind: "problem points",
+                target: format!("problem {}", row.get::<i32>(0)),
+                stored: row.get(1),
+                expected: row.get(2),
+            });
+        }
+
+        for row in SUBMISSION_POINTS_QUERY.execute(self, &[&in_queue, &compiling, &testing, &compilation_error]).await? {
+            issues.push(IntegrityIssue {
+                kind: "submission points",
+                target: format!("submission {}", row.get::<i32>(0)),
+                stored: row.get::<Option<i64>>(1).unwrap_or(0),
+                expected: row.get(2),
+            });
+        }
+
+        for row in SUBMISSION_TESTS_DONE_QUERY.execute(self, &[&in_queue, &compiling, &testing, &compilation_error]).await? {
+            issues.push(IntegrityIssue {
+                kind: "submission tests done",
+                target: format!("submission {}", row.get::<i32>(0)),
+                stored: row.get(1),
+                expected: row.get(2),
+            });
+        }
+
+        for row in USER_SCORES_QUERY.execute(self, &[]).await? {
+            issues.push(IntegrityIssue {
+                kind: "user score",
+                target: format!("user {}, contest {}, problem {}", row.get::<i32>(0), row.get::<i32>(1), row.get::<i32>(2)),
+                stored: row.get(3),
+                expected: row.get(4),
+            });
+        }
+
+        Ok(issues)
+    }
+
+    /// recomputes all derived values in one transaction, returns the issues that were fixed
+    pub async fn repair_integrity(&self) -> Result<Vec<IntegrityIssue>> {
+        static PROBLEM_POINTS_QUERY: DatabaseQuery =
+            DatabaseQuery::new("UPDATE problems SET points = (SELECT COALESCE(SUM(subtask_score), 0) FROM subtasks WHERE subtasks.problem_id = problems.problem_id)");
+        static SUBMISSIONS_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "UPDATE submissions SET
+                points = (SELECT COALESCE(SUM(points), 0) FROM subtask_results WHERE subtask_results.submission_id = submissions.submission_id),
+                tests_done = (SELECT COUNT(*) FROM test_results WHERE test_results.submission_id = submissions.submission_id)
+            WHERE result NOT IN ($1, $2, $3, $4)",
+        );
+        static USER_SCORE_TRIPLES_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT user_id, contest_id, problem_id FROM submissions WHERE user_id IS NOT NULL AND contest_id IS NOT NULL AND problem_id IS NOT NULL
+            UNION SELECT user_id, contest_id, problem_id FROM user_problem_scores",
+        );
+
+        let [in_queue, compiling, testing, compilation_error] = UNFINISHED_RESULTS.map(testing_result_to_i32);
+        let transaction = self.begin_transaction().await?;
+
+        let issues = transaction.check_integrity().await?;
+
+        PROBLEM_POINTS_QUERY.execute(&transaction, &[]).await?;
+        transaction.invalidate_all(|cache| &cache.problem_points)?;
+        SUBMISSIONS_QUERY.execute(&transaction, &[&in_queue, &compiling, &testing, &compilation_error]).await?;
+        for row in USER_SCORE_TRIPLES_QUERY.execute(&transaction, &[]).await? {
+            transaction.update_user_score_for_problem(row.get(0), row.get(1), row.get(2)).await?;
+        }
+
+        transaction.commit().await.map(|()| issues)
+    }
+}
@@ -0,0 +1,192 @@
+use crate::database::storage::Backend;
+use crate::database::{Database, DatabaseQuery};
+use anyhow::{anyhow, bail, Result};
+
+/// a numbered change of the database schema, every migration is applied exactly once and in order
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    // every backend has its own dialect, so every migration is written once for each of them
+    postgres_sql: &'static str,
+    sqlite_sql: &'static str,
+}
+
+impl Migration {
+    const fn sql(&self, backend: Backend) -> &'static str {
+        match backend {
+            Backend::Postgres => self.postgres_sql,
+            Backend::Sqlite => self.sqlite_sql,
+        }
+    }
+}
+
+/// all migrations, new ones have to be appended with the next version number and never changed afterwards
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial schema",
+        postgres_sql: include_str!("migrations/postgres/0001_initial_schema.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "cascading deletes",
+        postgres_sql: include_str!("migrations/postgres/0002_cascading_deletes.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0002_cascading_deletes.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "audit log",
+        postgres_sql: include_str!("migrations/postgres/0003_audit_log.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0003_audit_log.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "contest times",
+        postgres_sql: include_str!("migrations/postgres/0004_contest_times.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0004_contest_times.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "score improvement times",
+        postgres_sql: include_str!("migrations/postgres/0005_score_improvement_times.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0005_score_improvement_times.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "icpc contests",
+        postgres_sql: include_str!("migrations/postgres/0006_icpc_contests.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0006_icpc_contests.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "scoreboard freeze",
+        postgres_sql: include_str!("migrations/postgres/0007_scoreboard_freeze.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0007_scoreboard_freeze.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "windowed contests",
+        postgres_sql: include_str!("migrations/postgres/0008_windowed_contests.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0008_windowed_contests.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "problem positions",
+        postgres_sql: include_str!("migrations/postgres/0009_problem_positions.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0009_problem_positions.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "per contest scores",
+        postgres_sql: include_str!("migrations/postgres/0010_per_contest_scores.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0010_per_contest_scores.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "clarifications",
+        postgres_sql: include_str!("migrations/postgres/0011_clarifications.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0011_clarifications.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "announcements",
+        postgres_sql: include_str!("migrations/postgres/0012_announcements.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0012_announcements.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "teams",
+        postgres_sql: include_str!("migrations/postgres/0013_teams.sql"),
+        sqlite_sql: include_str!("migrations/sqlite/0013_teams.sql"),
+    },
+];
+
+impl Database {
+    async fn init_schema_version(&self) -> Result<()> {
+        self.batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_version (
+                version INT PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL
+            );",
+        )
+        .await
+    }
+
+    pub async fn get_schema_version(&self) -> Result<i32> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT COALESCE(MAX(version), 0) FROM schema_version");
+
+        self.init_schema_version().await?;
+
+        let rows = QUERY.execute(self, &[]).await?;
+        Ok(rows.first().ok_or_else(|| anyhow!("No schema version returned"))?.get(0))
+    }
+
+    pub async fn get_pending_migrations(&self) -> Result<Vec<&'static Migration>> {
+        let version = self.get_schema_version().await?;
+
+        let latest_version = MIGRATIONS.last().map_or(0, |migration| migration.version);
+        if version > latest_version {
+            bail!("The database schema version is {version}, but this version of CPS only knows migrations up to {latest_version}");
+        }
+
+        Ok(MIGRATIONS.iter().filter(|migration| migration.version > version).collect())
+    }
+
+    async fn apply_migration(&self, migration: &Migration) -> Result<()> {
+        static APPLIED_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT version FROM schema_version WHERE version = $1");
+        static INSERT_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO schema_version (version, name, applied_at) VALUES ($1, $2, $3)");
+        static FOREIGN_KEY_CHECK_QUERY: DatabaseQuery = DatabaseQuery::new("PRAGMA foreign_key_check");
+
+        let transaction = self.begin_transaction().await?;
+
+        // make sure that two servers starting at the same time do not apply the same migration,
+        // sqlite transactions already lock the whole database
+        if self.backend() == Backend::Postgres {
+            transaction.batch_execute("LOCK TABLE schema_version IN EXCLUSIVE MODE").await?;
+        }
+
+        if APPLIED_QUERY.execute(&transaction, &[&migration.version]).await?.is_empty() {
+            transaction
+                .batch_execute(migration.sql(self.backend()))
+                .await
+                .map_err(|err| anyhow!("Migration {} ({}) failed: {err}", migration.version, migration.name))?;
+            INSERT_QUERY.execute(&transaction, &[&migration.version, &migration.name, &chrono::Utc::now()]).await?;
+        }
+
+        // foreign keys are not enforced while sqlite migrates, so check them before committing
+        if self.backend() == Backend::Sqlite && !FOREIGN_KEY_CHECK_QUERY.execute(&transaction, &[]).await?.is_empty() {
+            bail!("Migration {} ({}) failed: it breaks foreign keys", migration.version, migration.name);
+        }
+
+        transaction.invalidate_everything()?;
+        transaction.commit().await
+    }
+
+    /// applies all pending migrations, each one in its own transaction
+    pub async fn apply_migrations(&self) -> Result<()> {
+        // sqlite can only change foreign keys by rebuilding tables, which only works while they are not enforced,
+        // this cannot be changed inside of a transaction
+        if self.backend() == Backend::Sqlite {
+            self.batch_execute("PRAGMA foreign_keys = OFF").await?;
+        }
+
+        let result = self.apply_pending_migrations().await;
+
+        if self.backend() == Backend::Sqlite {
+            self.batch_execute("PRAGMA foreign_keys = ON").await?;
+        }
+
+        result
+    }
+
+    async fn apply_pending_migrations(&self) -> Result<()> {
+        for migration in self.get_pending_migrations().await? {
+            println!("Applying migration {} ({})", migration.version, migration.name);
+            self.apply_migration(migration).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+-- the schema as it was before migrations were introduced
+-- IF NOT EXISTS is kept here so that existing databases can adopt it without changes
+
+CREATE TABLE IF NOT EXISTS users (
+    user_id SERIAL PRIMARY KEY,
+    username VARCHAR(50) UNIQUE NOT NULL,
+    password VARCHAR(100) NOT NULL,
+    is_admin BOOLEAN NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tokens (
+    token VARCHAR(255) NOT NULL,
+    expiration_date TIMESTAMPTZ NOT NULL,
+    user_id INT REFERENCES users(user_id)
+);
+
+CREATE TABLE IF NOT EXISTS contests (
+    contest_id SERIAL PRIMARY KEY,
+    contest_name VARCHAR(100) UNIQUE NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contest_participations (
+    contest_id INT REFERENCES contests(contest_id),
+    user_id INT REFERENCES users(user_id),
+    PRIMARY KEY (contest_id, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS problems (
+    problem_id SERIAL PRIMARY KEY,
+    problem_name VARCHAR(100) UNIQUE NOT NULL,
+    problem_description TEXT NOT NULL,
+    points INT NOT NULL,
+    time_limit INT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contest_problems (
+    contest_id INT REFERENCES contests(contest_id),
+    problem_id INT REFERENCES problems(problem_id),
+    PRIMARY KEY (contest_id, problem_id)
+);
+
+CREATE TABLE IF NOT EXISTS user_problem_scores (
+    user_id INT REFERENCES users(user_id),
+    problem_id INT REFERENCES problems(problem_id),
+    score INT NOT NULL,
+    PRIMARY KEY (user_id, problem_id)
+);
+
+CREATE TABLE IF NOT EXISTS submissions (
+    submission_id SERIAL PRIMARY KEY,
+    user_id INT REFERENCES users(user_id),
+    problem_id INT REFERENCES problems(problem_id),
+    code TEXT NOT NULL,
+    result INT NOT NULL,
+    points INT,
+    tests_done INT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS subtasks (
+    subtask_id SERIAL PRIMARY KEY,
+    problem_id INT REFERENCES problems(problem_id),
+    subtask_score INT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS tests (
+    test_id SERIAL PRIMARY KEY,
+    problem_id INT REFERENCES problems(problem_id),
+    input_data TEXT NOT NULL,
+    output_data TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS subtask_tests (
+    subtask_id INT REFERENCES subtasks(subtask_id),
+    test_id INT REFERENCES tests(test_id),
+    PRIMARY KEY (subtask_id, test_id)
+);
+
+CREATE TABLE IF NOT EXISTS subtask_results (
+    submission_id INT REFERENCES submissions(submission_id),
+    subtask_id INT REFERENCES subtasks(subtask_id),
+    points INT,
+    result INT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS test_results (
+    submission_id INT REFERENCES submissions(submission_id),
+    test_id INT REFERENCES tests(test_id),
+    result INT NOT NULL,
+    time INT
+);
@@ -0,0 +1,55 @@
+-- deleting a user, contest, problem or submission deletes everything that belongs to it
+
+ALTER TABLE tokens
+    DROP CONSTRAINT IF EXISTS tokens_user_id_fkey,
+    ADD CONSTRAINT tokens_user_id_fkey FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE;
+
+ALTER TABLE contest_participations
+    DROP CONSTRAINT IF EXISTS contest_participations_contest_id_fkey,
+    DROP CONSTRAINT IF EXISTS contest_participations_user_id_fkey,
+    ADD CONSTRAINT contest_participations_contest_id_fkey FOREIGN KEY (contest_id) REFERENCES contests(contest_id) ON DELETE CASCADE,
+    ADD CONSTRAINT contest_participations_user_id_fkey FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE;
+
+ALTER TABLE contest_problems
+    DROP CONSTRAINT IF EXISTS contest_problems_contest_id_fkey,
+    DROP CONSTRAINT IF EXISTS contest_problems_problem_id_fkey,
+    ADD CONSTRAINT contest_problems_contest_id_fkey FOREIGN KEY (contest_id) REFERENCES contests(contest_id) ON DELETE CASCADE,
+    ADD CONSTRAINT contest_problems_problem_id_fkey FOREIGN KEY (problem_id) REFERENCES problems(problem_id) ON DELETE CASCADE;
+
+ALTER TABLE user_problem_scores
+    DROP CONSTRAINT IF EXISTS user_problem_scores_user_id_fkey,
+    DROP CONSTRAINT IF EXISTS user_problem_scores_problem_id_fkey,
+    ADD CONSTRAINT user_problem_scores_user_id_fkey FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE,
+    ADD CONSTRAINT user_problem_scores_problem_id_fkey FOREIGN KEY (problem_id) REFERENCES problems(problem_id) ON DELETE CASCADE;
+
+ALTER TABLE submissions
+    DROP CONSTRAINT IF EXISTS submissions_user_id_fkey,
+    DROP CONSTRAINT IF EXISTS submissions_problem_id_fkey,
+    ADD CONSTRAINT submissions_user_id_fkey FOREIGN KEY (user_id) REFERENCES users(user_id) ON DELETE CASCADE,
+    ADD CONSTRAINT submissions_problem_id_fkey FOREIGN KEY (problem_id) REFERENCES problems(problem_id) ON DELETE CASCADE;
+
+ALTER TABLE subtasks
+    DROP CONSTRAINT IF EXISTS subtasks_problem_id_fkey,
+    ADD CONSTRAINT subtasks_problem_id_fkey FOREIGN KEY (problem_id) REFERENCES problems(problem_id) ON DELETE CASCADE;
+
+ALTER TABLE tests
+    DROP CONSTRAINT IF EXISTS tests_problem_id_fkey,
+    ADD CONSTRAINT tests_problem_id_fkey FOREIGN KEY (problem_id) REFERENCES problems(problem_id) ON DELETE CASCADE;
+
+ALTER TABLE subtask_tests
+    DROP CONSTRAINT IF EXISTS subtask_tests_subtask_id_fkey,
+    DROP CONSTRAINT IF EXISTS subtask_tests_test_id_fkey,
+    ADD CONSTRAINT subtask_tests_subtask_id_fkey FOREIGN KEY (subtask_id) REFERENCES subtasks(subtask_id) ON DELETE CASCADE,
+    ADD CONSTRAINT subtask_tests_test_id_fkey FOREIGN KEY (test_id) REFERENCES tests(test_id) ON DELETE CASCADE;
+
+ALTER TABLE subtask_results
+    DROP CONSTRAINT IF EXISTS subtask_results_submission_id_fkey,
+    DROP CONSTRAINT IF EXISTS subtask_results_subtask_id_fkey,
+    ADD CONSTRAINT subtask_results_submission_id_fkey FOREIGN KEY (submission_id) REFERENCES submissions(submission_id) ON DELETE CASCADE,
+    ADD CONSTRAINT subtask_results_subtask_id_fkey FOREIGN KEY (subtask_id) REFERENCES subtasks(subtask_id) ON DELETE CASCADE;
+
+ALTER TABLE test_results
+    DROP CONSTRAINT IF EXISTS test_results_submission_id_fkey,
+    DROP CONSTRAINT IF EXISTS test_results_test_id_fkey,
+    ADD CONSTRAINT test_results_submission_id_fkey FOREIGN KEY (submission_id) REFERENCES submissions(submission_id) ON DELETE CASCADE,
+    ADD CONSTRAINT test_results_test_id_fkey FOREIGN KEY (test_id) REFERENCES tests(test_id) ON DELETE CASCADE;
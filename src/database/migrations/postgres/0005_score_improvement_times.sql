@@ -0,0 +1,3 @@
+-- when the score of a user for a problem last increased, shown on the scoreboard
+
+ALTER TABLE user_problem_scores ADD COLUMN improved_at TIMESTAMPTZ;
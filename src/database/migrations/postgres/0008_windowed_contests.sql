@@ -0,0 +1,4 @@
+-- windowed contests give every participant the same duration from their own start within the contest times
+
+ALTER TABLE contests ADD COLUMN duration_minutes INT;
+ALTER TABLE contest_participations ADD COLUMN started_at TIMESTAMPTZ;
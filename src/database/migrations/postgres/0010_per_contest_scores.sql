@@ -0,0 +1,32 @@
+-- submissions belong to the contest they were made in and scores are kept per contest, so a problem can be reused in a later contest.
+-- existing submissions are assigned to the first contest of their problem that the user takes part in
+
+ALTER TABLE submissions ADD COLUMN contest_id INT REFERENCES contests(contest_id) ON DELETE SET NULL;
+UPDATE submissions SET contest_id = COALESCE(
+    (SELECT MIN(contest_problems.contest_id) FROM contest_problems
+        JOIN contest_participations ON contest_participations.contest_id = contest_problems.contest_id AND contest_participations.user_id = submissions.user_id
+        WHERE contest_problems.problem_id = submissions.problem_id),
+    (SELECT MIN(contest_problems.contest_id) FROM contest_problems WHERE contest_problems.problem_id = submissions.problem_id)
+);
+
+CREATE TABLE new_user_problem_scores (
+    user_id INT REFERENCES users(user_id) ON DELETE CASCADE,
+    contest_id INT REFERENCES contests(contest_id) ON DELETE CASCADE,
+    problem_id INT REFERENCES problems(problem_id) ON DELETE CASCADE,
+    score INT NOT NULL,
+    improved_at TIMESTAMPTZ,
+    PRIMARY KEY (user_id, contest_id, problem_id)
+);
+INSERT INTO new_user_problem_scores (user_id, contest_id, problem_id, score, improved_at)
+SELECT best.user_id, best.contest_id, best.problem_id, CAST(COALESCE(SUM(best.points), 0) AS INT), old.improved_at FROM (
+    SELECT submissions.user_id, submissions.contest_id, submissions.problem_id, MAX(subtask_results.points) AS points
+    FROM subtask_results
+    JOIN submissions ON submissions.submission_id = subtask_results.submission_id
+    JOIN subtasks ON subtasks.subtask_id = subtask_results.subtask_id AND subtasks.problem_id = submissions.problem_id
+    WHERE submissions.user_id IS NOT NULL AND submissions.contest_id IS NOT NULL
+    GROUP BY submissions.user_id, submissions.contest_id, submissions.problem_id, subtask_results.subtask_id
+) AS best
+LEFT JOIN user_problem_scores AS old ON old.user_id = best.user_id AND old.problem_id = best.problem_id
+GROUP BY best.user_id, best.contest_id, best.problem_id, old.improved_at;
+DROP TABLE user_problem_scores;
+ALTER TABLE new_user_problem_scores RENAME TO user_problem_scores;
@@ -0,0 +1,18 @@
+-- questions of contestants to the jury, an answer is either only for the asker or for everyone in the contest
+
+CREATE TABLE clarifications (
+    clarification_id SERIAL PRIMARY KEY,
+    contest_id INT NOT NULL REFERENCES contests(contest_id) ON DELETE CASCADE,
+    problem_id INT REFERENCES problems(problem_id) ON DELETE SET NULL,
+    user_id INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    question TEXT NOT NULL,
+    asked_at TIMESTAMPTZ NOT NULL,
+    answer TEXT,
+    answered_at TIMESTAMPTZ,
+    is_public BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX clarifications_contest_id ON clarifications (contest_id);
+
+-- answers newer than this are shown as new in the sidebar
+ALTER TABLE contest_participations ADD COLUMN clarifications_seen_at TIMESTAMPTZ;
@@ -0,0 +1,18 @@
+-- announcements of the admins to all participants of a contest, optionally about a single problem
+
+CREATE TABLE announcements (
+    announcement_id SERIAL PRIMARY KEY,
+    contest_id INT NOT NULL REFERENCES contests(contest_id) ON DELETE CASCADE,
+    problem_id INT REFERENCES problems(problem_id) ON DELETE CASCADE,
+    announcement_text TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX announcements_contest_id ON announcements (contest_id);
+
+-- which users have seen which announcements, the others are marked as new
+CREATE TABLE announcement_reads (
+    announcement_id INT REFERENCES announcements(announcement_id) ON DELETE CASCADE,
+    user_id INT REFERENCES users(user_id) ON DELETE CASCADE,
+    PRIMARY KEY (announcement_id, user_id)
+);
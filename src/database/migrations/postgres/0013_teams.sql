@@ -0,0 +1,7 @@
+-- teams are accounts of their own that participate in contests, their members act as the team
+
+ALTER TABLE users ADD COLUMN is_team BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN team_id INT REFERENCES users(user_id) ON DELETE SET NULL;
+
+-- the member who submitted, the submission itself belongs to the team
+ALTER TABLE submissions ADD COLUMN submitted_by INT REFERENCES users(user_id) ON DELETE SET NULL;
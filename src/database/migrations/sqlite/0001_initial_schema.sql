@@ -0,0 +1,89 @@
+-- the same schema as for postgres, sqlite has no SERIAL and stores timestamps as text
+
+CREATE TABLE users (
+    user_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username VARCHAR(50) UNIQUE NOT NULL,
+    password VARCHAR(100) NOT NULL,
+    is_admin BOOLEAN NOT NULL
+);
+
+CREATE TABLE tokens (
+    token VARCHAR(255) NOT NULL,
+    expiration_date TEXT NOT NULL,
+    user_id INT REFERENCES users(user_id)
+);
+
+CREATE TABLE contests (
+    contest_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    contest_name VARCHAR(100) UNIQUE NOT NULL
+);
+
+CREATE TABLE contest_participations (
+    contest_id INT REFERENCES contests(contest_id),
+    user_id INT REFERENCES users(user_id),
+    PRIMARY KEY (contest_id, user_id)
+);
+
+CREATE TABLE problems (
+    problem_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_name VARCHAR(100) UNIQUE NOT NULL,
+    problem_description TEXT NOT NULL,
+    points INT NOT NULL,
+    time_limit INT NOT NULL
+);
+
+CREATE TABLE contest_problems (
+    contest_id INT REFERENCES contests(contest_id),
+    problem_id INT REFERENCES problems(problem_id),
+    PRIMARY KEY (contest_id, problem_id)
+);
+
+CREATE TABLE user_problem_scores (
+    user_id INT REFERENCES users(user_id),
+    problem_id INT REFERENCES problems(problem_id),
+    score INT NOT NULL,
+    PRIMARY KEY (user_id, problem_id)
+);
+
+CREATE TABLE submissions (
+    submission_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INT REFERENCES users(user_id),
+    problem_id INT REFERENCES problems(problem_id),
+    code TEXT NOT NULL,
+    result INT NOT NULL,
+    points INT,
+    tests_done INT NOT NULL
+);
+
+CREATE TABLE subtasks (
+    subtask_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_id INT REFERENCES problems(problem_id),
+    subtask_score INT NOT NULL
+);
+
+CREATE TABLE tests (
+    test_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_id INT REFERENCES problems(problem_id),
+    input_data TEXT NOT NULL,
+    output_data TEXT NOT NULL
+);
+
+CREATE TABLE subtask_tests (
+    subtask_id INT REFERENCES subtasks(subtask_id),
+    test_id INT REFERENCES tests(test_id),
+    PRIMARY KEY (subtask_id, test_id)
+);
+
+CREATE TABLE subtask_results (
+    submission_id INT REFERENCES submissions(submission_id),
+    subtask_id INT REFERENCES subtasks(subtask_id),
+    points INT,
+    result INT NOT NULL
+);
+
+CREATE TABLE test_results (
+    submission_id INT REFERENCES submissions(submission_id),
+    test_id INT REFERENCES tests(test_id),
+    result INT NOT NULL,
+    time INT
+);
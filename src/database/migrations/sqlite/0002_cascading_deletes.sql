@@ -0,0 +1,129 @@
+-- deleting a user, contest, problem or submission deletes everything that belongs to it
+-- sqlite cannot change foreign keys, so every table is rebuilt, foreign keys are not enforced while migrating
+
+CREATE TABLE new_users (
+    user_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username VARCHAR(50) UNIQUE NOT NULL,
+    password VARCHAR(100) NOT NULL,
+    is_admin BOOLEAN NOT NULL
+);
+INSERT INTO new_users (user_id, username, password, is_admin) SELECT user_id, username, password, is_admin FROM users;
+DROP TABLE users;
+ALTER TABLE new_users RENAME TO users;
+
+CREATE TABLE new_contests (
+    contest_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    contest_name VARCHAR(100) UNIQUE NOT NULL
+);
+INSERT INTO new_contests (contest_id, contest_name) SELECT contest_id, contest_name FROM contests;
+DROP TABLE contests;
+ALTER TABLE new_contests RENAME TO contests;
+
+CREATE TABLE new_problems (
+    problem_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_name VARCHAR(100) UNIQUE NOT NULL,
+    problem_description TEXT NOT NULL,
+    points INT NOT NULL,
+    time_limit INT NOT NULL
+);
+INSERT INTO new_problems (problem_id, problem_name, problem_description, points, time_limit) SELECT problem_id, problem_name, problem_description, points, time_limit FROM problems;
+DROP TABLE problems;
+ALTER TABLE new_problems RENAME TO problems;
+
+CREATE TABLE new_submissions (
+    submission_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INT REFERENCES users(user_id) ON DELETE CASCADE,
+    problem_id INT REFERENCES problems(problem_id) ON DELETE CASCADE,
+    code TEXT NOT NULL,
+    result INT NOT NULL,
+    points INT,
+    tests_done INT NOT NULL
+);
+INSERT INTO new_submissions (submission_id, user_id, problem_id, code, result, points, tests_done) SELECT submission_id, user_id, problem_id, code, result, points, tests_done FROM submissions;
+DROP TABLE submissions;
+ALTER TABLE new_submissions RENAME TO submissions;
+
+CREATE TABLE new_subtasks (
+    subtask_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_id INT REFERENCES problems(problem_id) ON DELETE CASCADE,
+    subtask_score INT NOT NULL
+);
+INSERT INTO new_subtasks (subtask_id, problem_id, subtask_score) SELECT subtask_id, problem_id, subtask_score FROM subtasks;
+DROP TABLE subtasks;
+ALTER TABLE new_subtasks RENAME TO subtasks;
+
+CREATE TABLE new_tests (
+    test_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_id INT REFERENCES problems(problem_id) ON DELETE CASCADE,
+    input_data TEXT NOT NULL,
+    output_data TEXT NOT NULL
+);
+INSERT INTO new_tests (test_id, problem_id, input_data, output_data) SELECT test_id, problem_id, input_data, output_data FROM tests;
+DROP TABLE tests;
+ALTER TABLE new_tests RENAME TO tests;
+
+CREATE TABLE new_tokens (
+    token VARCHAR(255) NOT NULL,
+    expiration_date TEXT NOT NULL,
+    user_id INT REFERENCES users(user_id) ON DELETE CASCADE
+);
+INSERT INTO new_tokens (token, expiration_date, user_id) SELECT token, expiration_date, user_id FROM tokens;
+DROP TABLE tokens;
+ALTER TABLE new_tokens RENAME TO tokens;
+
+CREATE TABLE new_contest_participations (
+    contest_id INT REFERENCES contests(contest_id) ON DELETE CASCADE,
+    user_id INT REFERENCES users(user_id) ON DELETE CASCADE,
+    PRIMARY KEY (contest_id, user_id)
+);
+INSERT INTO new_contest_participations (contest_id, user_id) SELECT contest_id, user_id FROM contest_participations;
+DROP TABLE contest_participations;
+ALTER TABLE new_contest_participations RENAME TO contest_participations;
+
+CREATE TABLE new_contest_problems (
+    contest_id INT REFERENCES contests(contest_id) ON DELETE CASCADE,
+    problem_id INT REFERENCES problems(problem_id) ON DELETE CASCADE,
+    PRIMARY KEY (contest_id, problem_id)
+);
+INSERT INTO new_contest_problems (contest_id, problem_id) SELECT contest_id, problem_id FROM contest_problems;
+DROP TABLE contest_problems;
+ALTER TABLE new_contest_problems RENAME TO contest_problems;
+
+CREATE TABLE new_user_problem_scores (
+    user_id INT REFERENCES users(user_id) ON DELETE CASCADE,
+    problem_id INT REFERENCES problems(problem_id) ON DELETE CASCADE,
+    score INT NOT NULL,
+    PRIMARY KEY (user_id, problem_id)
+);
+INSERT INTO new_user_problem_scores (user_id, problem_id, score) SELECT user_id, problem_id, score FROM user_problem_scores;
+DROP TABLE user_problem_scores;
+ALTER TABLE new_user_problem_scores RENAME TO user_problem_scores;
+
+CREATE TABLE new_subtask_tests (
+    subtask_id INT REFERENCES subtasks(subtask_id) ON DELETE CASCADE,
+    test_id INT REFERENCES tests(test_id) ON DELETE CASCADE,
+    PRIMARY KEY (subtask_id, test_id)
+);
+INSERT INTO new_subtask_tests (subtask_id, test_id) SELECT subtask_id, test_id FROM subtask_tests;
+DROP TABLE subtask_tests;
+ALTER TABLE new_subtask_tests RENAME TO subtask_tests;
+
+CREATE TABLE new_subtask_results (
+    submission_id INT REFERENCES submissions(submission_id) ON DELETE CASCADE,
+    subtask_id INT REFERENCES subtasks(subtask_id) ON DELETE CASCADE,
+    points INT,
+    result INT NOT NULL
+);
+INSERT INTO new_subtask_results (submission_id, subtask_id, points, result) SELECT submission_id, subtask_id, points, result FROM subtask_results;
+DROP TABLE subtask_results;
+ALTER TABLE new_subtask_results RENAME TO subtask_results;
+
+CREATE TABLE new_test_results (
+    submission_id INT REFERENCES submissions(submission_id) ON DELETE CASCADE,
+    test_id INT REFERENCES tests(test_id) ON DELETE CASCADE,
+    result INT NOT NULL,
+    time INT
+);
+INSERT INTO new_test_results (submission_id, test_id, result, time) SELECT submission_id, test_id, result, time FROM test_results;
+DROP TABLE test_results;
+ALTER TABLE new_test_results RENAME TO test_results;
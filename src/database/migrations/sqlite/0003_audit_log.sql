@@ -0,0 +1,16 @@
+-- administrative actions, entries are kept when the user who did them is deleted
+
+CREATE TABLE audit_log (
+    audit_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INT REFERENCES users(user_id) ON DELETE SET NULL,
+    username VARCHAR(50) NOT NULL,
+    action VARCHAR(50) NOT NULL,
+    target TEXT NOT NULL,
+    ip_address TEXT NOT NULL,
+    before_summary TEXT NOT NULL,
+    after_summary TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX audit_log_user_id ON audit_log (user_id);
+CREATE INDEX audit_log_action ON audit_log (action);
@@ -0,0 +1,4 @@
+-- when submissions to a contest are accepted, a missing time means there is no limit on that side
+
+ALTER TABLE contests ADD COLUMN start_time TEXT;
+ALTER TABLE contests ADD COLUMN end_time TEXT;
@@ -0,0 +1,4 @@
+-- icpc contests rank by solved problems and the time it took to solve them
+
+ALTER TABLE submissions ADD COLUMN submitted_at TEXT;
+ALTER TABLE contests ADD COLUMN contest_type INT NOT NULL DEFAULT 1;
@@ -0,0 +1,4 @@
+-- the scoreboard hides results submitted after the freeze time until they are revealed one by one
+
+ALTER TABLE contests ADD COLUMN freeze_time TEXT;
+ALTER TABLE contests ADD COLUMN revealed_until INT;
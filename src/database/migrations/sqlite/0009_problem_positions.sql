@@ -0,0 +1,14 @@
+-- problems of a contest have an explicit order and a short label, existing problems keep their order by id
+
+ALTER TABLE contest_problems ADD COLUMN position INT NOT NULL DEFAULT 0;
+ALTER TABLE contest_problems ADD COLUMN label TEXT NOT NULL DEFAULT '';
+UPDATE contest_problems SET position = (
+    SELECT COUNT(*) FROM contest_problems AS earlier WHERE earlier.contest_id = contest_problems.contest_id AND earlier.problem_id < contest_problems.problem_id
+);
+-- the same letters as get_letter_label: A to Z, then AA to ZZ, then AAA and so on
+UPDATE contest_problems SET label = CASE
+    WHEN position < 26 THEN SUBSTR('ABCDEFGHIJKLMNOPQRSTUVWXYZ', position + 1, 1)
+    WHEN position < 702 THEN SUBSTR('ABCDEFGHIJKLMNOPQRSTUVWXYZ', position / 26, 1) || SUBSTR('ABCDEFGHIJKLMNOPQRSTUVWXYZ', position % 26 + 1, 1)
+    ELSE SUBSTR('ABCDEFGHIJKLMNOPQRSTUVWXYZ', (position - 702) / 676 + 1, 1) || SUBSTR('ABCDEFGHIJKLMNOPQRSTUVWXYZ', (position - 702) / 26 % 26 + 1, 1)
+        || SUBSTR('ABCDEFGHIJKLMNOPQRSTUVWXYZ', (position - 702) % 26 + 1, 1)
+END;
@@ -1,61 +1,174 @@
+pub mod announcement;
+pub mod audit;
+pub mod backup;
+pub mod cache;
+pub mod clarification;
 pub mod contest;
+pub mod integrity;
+pub mod migrations;
+mod pool;
+mod postgres;
 pub mod problem;
+pub mod scoreboard;
+mod sqlite;
+pub mod storage;
 pub mod submission;
 pub mod test;
 pub mod user;
 
-use anyhow::Result;
-use std::sync::{Arc, OnceLock};
-use tokio_postgres::types::ToSql;
-use tokio_postgres::{Row, Statement};
+use crate::database::cache::DatabaseCache;
+use crate::database::postgres::PostgresStorage;
+use crate::database::sqlite::SqliteStorage;
+use crate::database::storage::{Backend, Row, SqlParam, Storage, StorageTransaction};
+use crate::Config;
+use anyhow::{anyhow, bail, Result};
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+// a cache invalidation that waits until the changes of a transaction are visible to everyone
+type PendingInvalidation = Box<dyn FnOnce(&Database) -> Result<()> + Send>;
+
+// an open transaction that is shared by all clones of a database
+struct SharedTransaction {
+    // taken out when committed
+    storage: Mutex<Option<Box<dyn StorageTransaction>>>,
+    invalidations: std::sync::Mutex<Vec<PendingInvalidation>>,
+}
 
 #[derive(Clone)]
 pub struct Database {
-    postgres_client: Arc<tokio_postgres::Client>,
+    storage: Arc<dyn Storage>,
+    cache: Arc<DatabaseCache>,
+    // set if this database is bound to a transaction, then all queries run inside of it
+    transaction: Option<Arc<SharedTransaction>>,
 }
 
 impl Database {
-    pub async fn new(username: &str, password: &str, host: &str, db_name: &str) -> Result<Self> {
-        println!("connecting to database \"{host}\" with username \"{username}\"");
-        let (client, connection) = tokio_postgres::connect(&format!("host={host} user={username} password={password} dbname={db_name}"), tokio_postgres::NoTls).await?;
-
-        // Spawn a new task to process the connection in the background.
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("connection error: {e}");
-            }
-        });
+    pub async fn new(config: &Config) -> Result<Self> {
+        let storage: Arc<dyn Storage> = match config.db_backend.as_str() {
+            "postgres" => Arc::new(PostgresStorage::connect(config).await?),
+            "sqlite" => Arc::new(SqliteStorage::open(&config.db_file, Duration::from_secs(config.db_acquire_timeout))?),
+            backend => bail!("Unknown database backend \"{backend}\", it has to be \"postgres\" or \"sqlite\""),
+        };
+
+        Ok(Self {
+            storage,
+            cache: Arc::new(DatabaseCache::new()),
+            transaction: None,
+        })
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.storage.backend()
+    }
+
+    /// the number of queries executed since the server started
+    pub fn get_query_count(&self) -> u64 {
+        self.storage.get_query_count()
+    }
+
+    /// starts a transaction, everything done through the returned transaction is only saved once it is committed,
+    /// if it is dropped without being committed, everything is rolled back.
+    /// starting a transaction inside of a transaction just continues the outer one.
+    pub async fn begin_transaction(&self) -> Result<Transaction> {
+        if self.transaction.is_some() {
+            return Ok(Transaction { database: self.clone(), owner: false });
+        }
+
+        Ok(Transaction {
+            database: Self {
+                storage: self.storage.clone(),
+                cache: self.cache.clone(),
+                transaction: Some(Arc::new(SharedTransaction {
+                    storage: Mutex::new(Some(self.storage.begin().await?)),
+                    invalidations: std::sync::Mutex::new(Vec::new()),
+                })),
+            },
+            owner: true,
+        })
+    }
 
-        Ok(Self { postgres_client: Arc::new(client) })
+    /// executes one or more statements that are not prepared, used for schema changes
+    pub async fn batch_execute(&self, sql: &str) -> Result<()> {
+        if let Some(transaction) = &self.transaction {
+            return transaction
+                .storage
+                .lock()
+                .await
+                .as_mut()
+                .ok_or_else(|| anyhow!("The transaction was already committed"))?
+                .batch_execute(sql)
+                .await;
+        }
+
+        self.storage.batch_execute(sql).await
+    }
+}
+
+/// a database bound to an open transaction, see `Database::begin_transaction`
+pub struct Transaction {
+    database: Database,
+    // only the outermost transaction commits
+    owner: bool,
+}
+
+impl Transaction {
+    pub async fn commit(self) -> Result<()> {
+        if self.owner {
+            if let Some(transaction) = &self.database.transaction {
+                let storage = transaction.storage.lock().await.take().ok_or_else(|| anyhow!("The transaction was already committed"))?;
+                storage.commit().await?;
+                // only now other requests can read the changed values, so only now they can be forgotten
+                let invalidations = std::mem::take(&mut *transaction.invalidations.lock().map_err(|e| anyhow!("{e}"))?);
+                let database = Database {
+                    storage: self.database.storage.clone(),
+                    cache: self.database.cache.clone(),
+                    transaction: None,
+                };
+                for invalidation in invalidations {
+                    invalidation(&database)?;
+                }
+            }
+        }
+        Ok(())
     }
+}
+
+impl Deref for Transaction {
+    type Target = Database;
 
-    pub fn get_postgres_client(&self) -> &tokio_postgres::Client {
-        &self.postgres_client
+    fn deref(&self) -> &Database {
+        &self.database
     }
 }
 
-/// this is a struct that is static and holds a prepared query to the database
+/// this is a struct that is static and holds a query to the database,
+/// the query is prepared once for every connection it runs on
 pub struct DatabaseQuery {
     query: &'static str,
-    prepared: OnceLock<Statement>,
 }
 
 impl DatabaseQuery {
     pub const fn new(query: &'static str) -> Self {
-        Self { query, prepared: OnceLock::new() }
+        Self { query }
     }
 
-    async fn get_query(&self, database: &Database) -> Result<Statement> {
-        if let Some(prepared) = self.prepared.get() {
-            return Ok(prepared.clone());
-        }
+    pub async fn execute(&self, database: &Database, params: &[&(dyn SqlParam + Sync)]) -> Result<Vec<Row>> {
+        let params = params.iter().map(|param| param.to_value()).collect();
 
-        let prepared = database.get_postgres_client().prepare(self.query).await?;
-        Ok(self.prepared.get_or_init(|| prepared.clone()).clone())
-    }
+        if let Some(transaction) = &database.transaction {
+            return transaction
+                .storage
+                .lock()
+                .await
+                .as_mut()
+                .ok_or_else(|| anyhow!("The transaction was already committed"))?
+                .query(self.query, params)
+                .await;
+        }
 
-    pub async fn execute(&self, database: &Database, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>> {
-        let query = self.get_query(database).await?;
-        Ok(database.get_postgres_client().query(&query, params).await?)
+        database.storage.query(self.query, params).await
     }
 }
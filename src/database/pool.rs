@@ -0,0 +1,176 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Client, Row, Statement};
+
+// first wait before trying to connect again, it doubles with every failed attempt
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+pub struct PoolConfig {
+    pub connection_string: String,
+    pub size: usize,
+    pub connect_timeout: Duration,
+    pub acquire_timeout: Duration,
+}
+
+/// a connection to postgres together with the statements that were prepared on it
+struct Connection {
+    client: Client,
+    statements: HashMap<&'static str, Statement>,
+}
+
+/// a pool of connections to postgres, dead connections are thrown away and replaced with new ones
+pub struct ConnectionPool {
+    config: PoolConfig,
+    idle: Mutex<Vec<Connection>>,
+    // limits how many connections can be open at once
+    permits: Arc<Semaphore>,
+    // number of queries executed, used to find pages that query too often
+    queries: AtomicU64,
+}
+
+impl ConnectionPool {
+    pub fn new(config: PoolConfig) -> Arc<Self> {
+        Arc::new(Self {
+            permits: Arc::new(Semaphore::new(config.size)),
+            config,
+            idle: Mutex::new(Vec::new()),
+            queries: AtomicU64::new(0),
+        })
+    }
+
+    pub fn get_query_count(&self) -> u64 {
+        self.queries.load(Ordering::Relaxed)
+    }
+
+    async fn try_connect(&self) -> Result<Connection> {
+        let (client, connection) = tokio::time::timeout(self.config.connect_timeout, tokio_postgres::connect(&self.config.connection_string, tokio_postgres::NoTls))
+            .await
+            .map_err(|_elapsed| anyhow!("connecting timed out"))??;
+
+        // Spawn a new task to process the connection in the background.
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                eprintln!("connection error: {e}");
+            }
+        });
+
+        Ok(Connection { client, statements: HashMap::new() })
+    }
+
+    /// connects to the database, retrying with a growing delay until the acquire timeout runs out
+    async fn connect(&self, deadline: Instant) -> Result<Connection> {
+        let mut backoff = INITIAL_BACKOFF;
+        loop {
+            let err = match self.try_connect().await {
+                Ok(connection) => return Ok(connection),
+                Err(err) => err,
+            };
+
+            if Instant::now() + backoff > deadline {
+                return Err(anyhow!("Could not connect to the database: {err}"));
+            }
+
+            eprintln!("Could not connect to the database, retrying in {}ms: {err}", backoff.as_millis());
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    pub async fn get(self: &Arc<Self>) -> Result<PooledConnection> {
+        let deadline = Instant::now() + self.config.acquire_timeout;
+
+        let permit = tokio::time::timeout_at(deadline, self.permits.clone().acquire_owned())
+            .await
+            .map_err(|_elapsed| anyhow!("Timed out while waiting for a database connection"))??;
+
+        let idle = loop {
+            let connection = self.idle.lock().map_err(|e| anyhow!("{e}"))?.pop();
+            match connection {
+                // the connection died while it was idle, for example because postgres restarted
+                Some(connection) if connection.client.is_closed() => {}
+                connection => break connection,
+            }
+        };
+
+        let connection = match idle {
+            Some(connection) => connection,
+            None => self.connect(deadline).await?,
+        };
+
+        Ok(PooledConnection {
+            pool: self.clone(),
+            connection: Some(connection),
+            in_transaction: false,
+            _permit: permit,
+        })
+    }
+}
+
+/// a connection that is borrowed from the pool and returned to it when dropped
+pub struct PooledConnection {
+    pool: Arc<ConnectionPool>,
+    connection: Option<Connection>,
+    // set while a transaction is open on this connection
+    in_transaction: bool,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl PooledConnection {
+    fn connection(&mut self) -> Result<&mut Connection> {
+        self.connection.as_mut().ok_or_else(|| anyhow!("The connection was already returned to the pool"))
+    }
+
+    pub fn client(&self) -> Result<&Client> {
+        Ok(&self.connection.as_ref().ok_or_else(|| anyhow!("The connection was already returned to the pool"))?.client)
+    }
+
+    /// prepares the query once per connection and reuses the statement afterwards
+    async fn prepare_cached(&mut self, query: &'static str) -> Result<Statement> {
+        let connection = self.connection()?;
+        if let Some(statement) = connection.statements.get(query) {
+            return Ok(statement.clone());
+        }
+
+        let statement = connection.client.prepare(query).await?;
+        connection.statements.insert(query, statement.clone());
+        Ok(statement)
+    }
+
+    pub async fn query(&mut self, query: &'static str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>> {
+        self.pool.queries.fetch_add(1, Ordering::Relaxed);
+        let statement = self.prepare_cached(query).await?;
+        Ok(self.connection()?.client.query(&statement, params).await?)
+    }
+
+    pub async fn begin(&mut self) -> Result<()> {
+        self.client()?.batch_execute("BEGIN").await?;
+        self.in_transaction = true;
+        Ok(())
+    }
+
+    pub async fn commit(&mut self) -> Result<()> {
+        self.client()?.batch_execute("COMMIT").await?;
+        self.in_transaction = false;
+        Ok(())
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            // a connection with an unfinished transaction is closed instead, which makes postgres roll the transaction back
+            if !connection.client.is_closed() && !self.in_transaction {
+                if let Ok(mut idle) = self.pool.idle.lock() {
+                    idle.push(connection);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::database::pool::{ConnectionPool, PoolConfig, PooledConnection};
+use crate::database::storage::{Backend, Row, Storage, StorageTransaction, Value};
+use crate::Config;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use bytes::BytesMut;
+use chrono::{DateTime, Utc};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_postgres::types::{to_sql_checked, IsNull, ToSql, Type};
+
+impl ToSql for Value {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        // the variant has to fit the type of the parameter, like in `read_value`, otherwise the wrong bytes would be sent
+        match (self, ty) {
+            (Self::Null, _) => Ok(IsNull::Yes),
+            // integers have to be sent with the exact size of the column
+            (Self::Integer(value), &Type::INT2) => i16::try_from(*value)?.to_sql(ty, out),
+            (Self::Integer(value), &Type::INT4) => i32::try_from(*value)?.to_sql(ty, out),
+            (Self::Integer(value), &Type::INT8) => value.to_sql(ty, out),
+            (Self::Boolean(value), &Type::BOOL) => value.to_sql(ty, out),
+            (Self::Text(value), &Type::TEXT | &Type::VARCHAR | &Type::BPCHAR | &Type::NAME) => value.to_sql(ty, out),
+            (Self::Timestamp(value), &Type::TIMESTAMPTZ) => value.to_sql(ty, out),
+            _ => Err(format!("Cannot send {self:?} as a parameter of type {ty}").into()),
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        matches!(
+            *ty,
+            Type::BOOL | Type::INT2 | Type::INT4 | Type::INT8 | Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::TIMESTAMPTZ
+        )
+    }
+
+    to_sql_checked!();
+}
+
+fn read_value(row: &tokio_postgres::Row, index: usize, ty: &Type) -> Result<Value> {
+    let value = match *ty {
+        Type::BOOL => row.try_get::<_, Option<bool>>(index)?.map(Value::Boolean),
+        Type::INT2 => row.try_get::<_, Option<i16>>(index)?.map(|value| Value::Integer(value.into())),
+        Type::INT4 => row.try_get::<_, Option<i32>>(index)?.map(|value| Value::Integer(value.into())),
+        Type::INT8 => row.try_get::<_, Option<i64>>(index)?.map(Value::Integer),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => row.try_get::<_, Option<String>>(index)?.map(Value::Text),
+        Type::TIMESTAMPTZ => row.try_get::<_, Option<DateTime<Utc>>>(index)?.map(Value::Timestamp),
+        _ => bail!("Unsupported column type {ty}"),
+    };
+
+    Ok(value.unwrap_or(Value::Null))
+}
+
+async fn query(connection: &mut PooledConnection, query: &'static str, params: &[Value]) -> Result<Vec<Row>> {
+    let params: Vec<&(dyn ToSql + Sync)> = params.iter().map(|param| param as &(dyn ToSql + Sync)).collect();
+
+    let mut rows = Vec::new();
+    for row in connection.query(query, &params).await? {
+        let mut values = Vec::new();
+        for (index, column) in row.columns().iter().enumerate() {
+            values.push(read_value(&row, index, column.type_())?);
+        }
+        rows.push(Row::new(values));
+    }
+
+    Ok(rows)
+}
+
+pub struct PostgresStorage {
+    pool: Arc<ConnectionPool>,
+}
+
+impl PostgresStorage {
+    pub async fn connect(config: &Config) -> Result<Self> {
+        println!("connecting to database \"{}\" with username \"{}\"", config.db_host, config.db_username);
+
+        let pool = ConnectionPool::new(PoolConfig {
+            connection_string: format!("host={} user={} password={} dbname={}", config.db_host, config.db_username, config.db_password, config.db_name),
+            size: config.db_pool_size,
+            connect_timeout: Duration::from_secs(config.db_connect_timeout),
+            acquire_timeout: Duration::from_secs(config.db_acquire_timeout),
+        });
+
+        // connect right away, so that a wrong configuration is reported at startup
+        drop(pool.get().await?);
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl Storage for PostgresStorage {
+    fn backend(&self) -> Backend {
+        Backend::Postgres
+    }
+
+    async fn query(&self, query_string: &'static str, params: Vec<Value>) -> Result<Vec<Row>> {
+        query(&mut self.pool.get().await?, query_string, &params).await
+    }
+
+    async fn batch_execute(&self, sql: &str) -> Result<()> {
+        self.pool.get().await?.client()?.batch_execute(sql).await?;
+        Ok(())
+    }
+
+    async fn begin(&self) -> Result<Box<dyn StorageTransaction>> {
+        let mut transaction = Box::new(PostgresTransaction { connection: self.pool.get().await? });
+        transaction.connection.begin().await?;
+
+        Ok(transaction)
+    }
+
+    fn get_query_count(&self) -> u64 {
+        self.pool.get_query_count()
+    }
+}
+
+/// the whole transaction runs on one connection, which is closed if the transaction is not committed
+struct PostgresTransaction {
+    connection: PooledConnection,
+}
+
+#[async_trait]
+impl StorageTransaction for PostgresTransaction {
+    async fn query(&mut self, query_string: &'static str, params: Vec<Value>) -> Result<Vec<Row>> {
+        query(&mut self.connection, query_string, &params).await
+    }
+
+    async fn batch_execute(&mut self, sql: &str) -> Result<()> {
+        self.connection.client()?.batch_execute(sql).await?;
+        Ok(())
+    }
+
+    async fn commit(mut self: Box<Self>) -> Result<()> {
+        self.connection.commit().await
+    }
+}
@@ -5,63 +5,56 @@ use anyhow::{anyhow, Result};
 
 pub type ProblemId = i32;
 
-impl Database {
-    pub async fn init_problems(&self) -> Result<()> {
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS problems (
-                    problem_id SERIAL PRIMARY KEY,
-                    problem_name VARCHAR(100) UNIQUE NOT NULL,
-                    problem_description TEXT NOT NULL,
-                    points INT NOT NULL,
-                    time_limit INT NOT NULL
-                );",
-                &[],
-            )
-            .await?;
-
-        // add table of contest problems
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS contest_problems (
-                    contest_id INT REFERENCES contests(contest_id),
-                    problem_id INT REFERENCES problems(problem_id),
-                    PRIMARY KEY (contest_id, problem_id)
-                );",
-                &[],
-            )
-            .await?;
-
-        // add table of user scores for problems
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS user_problem_scores (
-                    user_id INT REFERENCES users(user_id),
-                    problem_id INT REFERENCES problems(problem_id),
-                    score INT NOT NULL,
-                    PRIMARY KEY (user_id, problem_id)
-                );",
-                &[],
-            )
-            .await?;
+/// the label of the problem at the given position: A, B, ..., Z, AA, AB, ...
+pub fn get_letter_label(position: usize) -> String {
+    let mut label = Vec::new();
+    let mut rest = position + 1;
+    while rest > 0 {
+        rest -= 1;
+        label.push(b'A' + u8::try_from(rest % 26).unwrap_or(0));
+        rest /= 26;
+    }
+    label.reverse();
+    String::from_utf8_lossy(&label).into_owned()
+}
 
-        Ok(())
+/// the position of a letter label, the inverse of `get_letter_label`. None if the label is not made of capital letters
+pub fn get_letter_position(label: &str) -> Option<usize> {
+    if label.is_empty() || !label.bytes().all(|letter| letter.is_ascii_uppercase()) {
+        return None;
     }
+    label
+        .bytes()
+        .try_fold(0_usize, |position, letter| position.checked_mul(26)?.checked_add(usize::from(letter - b'A') + 1))
+        .map(|position| position - 1)
+}
 
-    pub async fn is_problem_id_valid(&self, problem_id: ProblemId) -> bool {
-        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT problem_id FROM problems WHERE problem_id = $1");
+/// the problem as it is shown next to clarifications and announcements, empty if there is no problem
+pub fn get_problem_title(label: Option<String>, problem_name: Option<String>) -> String {
+    match (label, problem_name) {
+        (Some(label), Some(problem_name)) => format!("{label}. {problem_name}"),
+        (None, Some(problem_name)) => problem_name,
+        _ => String::new(),
+    }
+}
 
-        QUERY.execute(self, &[&problem_id]).await.ok().is_some_and(|rows| !rows.is_empty())
+impl Database {
+    pub async fn is_problem_id_valid(&self, problem_id: ProblemId) -> bool {
+        self.get_problem_name(problem_id).await.is_ok()
     }
 
     pub async fn get_problem_name(&self, problem_id: ProblemId) -> Result<String> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT problem_name FROM problems WHERE problem_id = $1");
 
-        Ok(QUERY.execute(self, &[&problem_id]).await?.first().ok_or_else(|| anyhow!("No problem with id {}", problem_id))?.get(0))
+        self.cached(|cache| &cache.problem_names, problem_id, async {
+            Ok(QUERY.execute(self, &[&problem_id]).await?.first().ok_or_else(|| anyhow!("No problem with id {}", problem_id))?.get(0))
+        })
+        .await
     }
 
+    /// the problems of a contest in their order
     pub async fn get_problems_for_contest(&self, contest_id: ContestId) -> Result<Vec<ProblemId>> {
-        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT problem_id FROM contest_problems WHERE contest_id = $1");
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT problem_id FROM contest_problems WHERE contest_id = $1 ORDER BY position, problem_id");
 
         let rows = QUERY.execute(self, &[&contest_id]).await?;
 
@@ -73,6 +66,35 @@ impl Database {
         Ok(result)
     }
 
+    /// the problems of a contest with their labels and names, in their order
+    pub async fn get_problem_names_for_contest(&self, contest_id: ContestId) -> Result<Vec<(ProblemId, String, String)>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT problems.problem_id, contest_problems.label, problems.problem_name FROM contest_problems
+            JOIN problems ON problems.problem_id = contest_problems.problem_id
+            WHERE contest_problems.contest_id = $1 ORDER BY contest_problems.position, problems.problem_id",
+        );
+
+        Ok(QUERY.execute(self, &[&contest_id]).await?.iter().map(|row| (row.get(0), row.get(1), row.get(2))).collect())
+    }
+
+    /// the label of a problem in a contest, None if the problem is not in the contest
+    pub async fn get_problem_label(&self, contest_id: ContestId, problem_id: ProblemId) -> Result<Option<String>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT label FROM contest_problems WHERE contest_id = $1 AND problem_id = $2");
+
+        Ok(QUERY.execute(self, &[&contest_id, &problem_id]).await?.first().map(|row| row.get(0)))
+    }
+
+    /// stores the order and the labels of the problems of a contest, the problems have to be in the contest
+    pub async fn set_problem_order(&self, contest_id: ContestId, problems: &[(ProblemId, String)]) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE contest_problems SET position = $3, label = $4 WHERE contest_id = $1 AND problem_id = $2");
+
+        let transaction = self.begin_transaction().await?;
+        for (position, (problem_id, label)) in problems.iter().enumerate() {
+            QUERY.execute(&transaction, &[&contest_id, problem_id, &i32::try_from(position)?, label]).await?;
+        }
+        transaction.commit().await
+    }
+
     pub async fn add_problem(&self, problem_name: &str, problem_description: &str, time_limit: i32) -> Result<ProblemId> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO problems (problem_name, problem_description, points, time_limit) VALUES ($1, $2, $3, $4) RETURNING problem_id");
 
@@ -84,12 +106,14 @@ impl Database {
             .get(0))
     }
 
+    /// deletes the problem with everything that belongs to it, like its tests, submissions and scores
     pub async fn remove_problem(&self, problem_id: ProblemId) -> Result<()> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("DELETE FROM problems WHERE problem_id = $1");
 
-        self.delete_all_subtasks_and_tests_for_problem(problem_id).await?;
-
         QUERY.execute(self, &[&problem_id]).await?;
+        self.invalidate(|cache| &cache.problem_names, &problem_id)?;
+        self.invalidate(|cache| &cache.problem_points, &problem_id)?;
+        self.invalidate(|cache| &cache.problem_time_limits, &problem_id)?;
 
         Ok(())
     }
@@ -112,10 +136,23 @@ impl Database {
         self.add_problem(problem_name, problem_description, time_limit).await
     }
 
+    /// the problem is added at the end, its label continues the numbers or letters of the last problem
     pub async fn add_problem_to_contest(&self, contest_id: ContestId, problem_id: ProblemId) -> Result<()> {
-        static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO contest_problems (contest_id, problem_id) VALUES ($1, $2)");
-
-        QUERY.execute(self, &[&contest_id, &problem_id]).await?;
+        static LAST_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT position, label FROM contest_problems WHERE contest_id = $1 ORDER BY position DESC, problem_id DESC LIMIT 1");
+        static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO contest_problems (contest_id, problem_id, position, label) VALUES ($1, $2, $3, $4)");
+
+        let last = LAST_QUERY.execute(self, &[&contest_id]).await?.first().map(|row| (row.get::<i32>(0), row.get::<String>(1)));
+        let position = last.as_ref().map_or(0, |(position, _)| position + 1);
+        let last_label = last.map(|(_, label)| label).unwrap_or_default();
+        let label = if let Ok(number) = last_label.parse::<i32>() {
+            (number + 1).to_string()
+        } else if let Some(letter_position) = get_letter_position(&last_label) {
+            get_letter_label(letter_position + 1)
+        } else {
+            get_letter_label(usize::try_from(position)?)
+        };
+
+        QUERY.execute(self, &[&contest_id, &problem_id, &position, &label]).await?;
         Ok(())
     }
 
@@ -135,40 +172,55 @@ impl Database {
     pub async fn get_problem_total_points(&self, problem_id: ProblemId) -> Result<i32> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT points FROM problems WHERE problem_id = $1");
 
-        Ok(QUERY.execute(self, &[&problem_id]).await?.first().ok_or_else(|| anyhow!("No problem with id {}", problem_id))?.get(0))
+        self.cached(|cache| &cache.problem_points, problem_id, async {
+            Ok(QUERY.execute(self, &[&problem_id]).await?.first().ok_or_else(|| anyhow!("No problem with id {}", problem_id))?.get(0))
+        })
+        .await
     }
 
     pub async fn get_problem_time_limit(&self, problem_id: ProblemId) -> Result<i32> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT time_limit FROM problems WHERE problem_id = $1");
 
-        Ok(QUERY.execute(self, &[&problem_id]).await?.first().ok_or_else(|| anyhow!("No problem with id {}", problem_id))?.get(0))
+        self.cached(|cache| &cache.problem_time_limits, problem_id, async {
+            Ok(QUERY.execute(self, &[&problem_id]).await?.first().ok_or_else(|| anyhow!("No problem with id {}", problem_id))?.get(0))
+        })
+        .await
     }
 
-    pub async fn get_user_score_for_problem(&self, user_id: i32, problem_id: ProblemId) -> Result<i32> {
-        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT score FROM user_problem_scores WHERE user_id = $1 AND problem_id = $2");
+    pub async fn get_user_score_for_problem(&self, user_id: i32, contest_id: ContestId, problem_id: ProblemId) -> Result<i32> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT score FROM user_problem_scores WHERE user_id = $1 AND contest_id = $2 AND problem_id = $3");
 
-        Ok(QUERY.execute(self, &[&user_id, &problem_id]).await?.first().map_or(0, |row| row.get(0)))
+        Ok(QUERY.execute(self, &[&user_id, &contest_id, &problem_id]).await?.first().map_or(0, |row| row.get(0)))
     }
 
-    pub async fn update_user_score_for_problem(&self, user_id: i32, problem_id: ProblemId) -> Result<()> {
-        static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO user_problem_scores (user_id, problem_id, score) VALUES ($1, $2, $3) ON CONFLICT (user_id, problem_id) DO UPDATE SET score = $3");
-
-        let submissions = self.get_submissions_by_user_for_problem(user_id, problem_id).await?;
-        let subtasks = self.get_subtasks_for_problem(problem_id).await?;
-
-        let mut score = 0;
-
-        for subtask in subtasks {
-            let mut subtask_score = 0;
-
-            for submission in &submissions {
-                subtask_score = subtask_score.max(self.get_subtask_points_result(*submission, subtask).await?.unwrap_or(0));
-            }
-
-            score += subtask_score;
-        }
-
-        QUERY.execute(self, &[&user_id, &problem_id, &score]).await?;
+    /// the score is the sum of the best points the user got for every subtask over all submissions in the contest,
+    /// it was reached when the last of these subtasks got its best points, so that is when the score improved
+    pub async fn update_user_score_for_problem(&self, user_id: i32, contest_id: ContestId, problem_id: ProblemId) -> Result<()> {
+        // sqlite needs the WHERE to tell the ON CONFLICT apart from a join
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "INSERT INTO user_problem_scores (user_id, contest_id, problem_id, score, improved_at)
+            SELECT $1, $2, $3, CAST(COALESCE(SUM(best_points), 0) AS INT), MAX(reached_at) FROM (
+                SELECT best_points, CASE WHEN best_points > 0 THEN (
+                    SELECT MIN(submissions.submitted_at)
+                    FROM subtask_results
+                    JOIN submissions ON submissions.submission_id = subtask_results.submission_id
+                    WHERE submissions.user_id = $1 AND submissions.contest_id = $2 AND submissions.problem_id = $3
+                        AND subtask_results.subtask_id = best_subtask_points.subtask_id AND subtask_results.points = best_subtask_points.best_points
+                ) END AS reached_at
+                FROM (
+                    SELECT subtask_results.subtask_id, MAX(subtask_results.points) AS best_points
+                    FROM subtask_results
+                    JOIN submissions ON submissions.submission_id = subtask_results.submission_id
+                    JOIN subtasks ON subtasks.subtask_id = subtask_results.subtask_id
+                    WHERE submissions.user_id = $1 AND submissions.contest_id = $2 AND submissions.problem_id = $3 AND subtasks.problem_id = $3
+                    GROUP BY subtask_results.subtask_id
+                ) AS best_subtask_points
+            ) AS reached_subtask_points
+            WHERE true
+            ON CONFLICT (user_id, contest_id, problem_id) DO UPDATE SET score = EXCLUDED.score, improved_at = EXCLUDED.improved_at",
+        );
+
+        QUERY.execute(self, &[&user_id, &contest_id, &problem_id]).await?;
 
         Ok(())
     }
@@ -184,7 +236,7 @@ impl Database {
         static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE problems SET problem_name = $2 WHERE problem_id = $1");
 
         QUERY.execute(self, &[&problem_id, &problem_name]).await?;
-        Ok(())
+        self.invalidate(|cache| &cache.problem_names, &problem_id)
     }
 
     pub async fn problem_with_name_exists(&self, problem_name: &str) -> bool {
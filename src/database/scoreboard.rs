@@ -0,0 +1,366 @@
+use crate::database::contest::{ContestId, ContestTimes, ContestType};
+use crate::database::problem::ProblemId;
+use crate::database::submission::{i32_to_testing_result, SubmissionId, TestingResult};
+use crate::database::user::UserId;
+use crate::database::{Database, DatabaseQuery};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// minutes added to the penalty for every rejected attempt before a problem is solved
+pub const ICPC_PENALTY_MINUTES: i64 = 20;
+
+/// a problem of the scoreboard with its label, name and max points
+pub struct ScoreboardProblem {
+    pub problem_id: ProblemId,
+    pub label: String,
+    pub problem_name: String,
+    pub max_points: i32,
+}
+
+/// the result of a participant for one problem
+#[derive(Clone, Default)]
+pub struct ScoreboardCell {
+    /// points in ioi contests, 1 for a solved problem in icpc contests
+    pub score: i32,
+    /// rejected attempts before the problem was solved, only counted in icpc contests
+    pub rejected_attempts: i32,
+    /// minutes from the start of the contest until the problem was solved, 0 if the contest has no start time
+    pub solve_minute: Option<i64>,
+    pub is_first_solve: bool,
+    /// submissions that are hidden by the freeze
+    pub pending: i32,
+}
+
+/// a participant of the contest, cells are in the same order as the problems of the scoreboard
+pub struct ScoreboardRow {
+    pub rank: usize,
+    pub user_id: UserId,
+    pub username: String,
+    pub cells: Vec<ScoreboardCell>,
+    /// points in ioi contests, solved problems in icpc contests
+    pub total: i32,
+    /// always 0 in ioi contests
+    pub penalty: i64,
+    pub last_improvement: Option<DateTime<Utc>>,
+    /// the personal start in windowed contests
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+impl ScoreboardRow {
+    /// minutes from the personal start until the last improvement
+    pub fn elapsed_minutes(&self) -> Option<i64> {
+        self.last_improvement
+            .zip(self.started_at)
+            .map(|(last_improvement, started_at)| (last_improvement - started_at).num_minutes())
+    }
+}
+
+pub struct Scoreboard {
+    pub contest_type: ContestType,
+    pub problems: Vec<ScoreboardProblem>,
+    pub rows: Vec<ScoreboardRow>,
+    pub is_windowed: bool,
+    /// the time from which results of other participants are hidden, None if the viewer sees everything
+    pub frozen_since: Option<DateTime<Utc>>,
+}
+
+/// a submission made during the freeze that is not revealed yet
+pub struct FrozenSubmission {
+    pub submission_id: SubmissionId,
+    pub username: String,
+    pub problem_name: String,
+}
+
+/// decides which submissions a viewer of the scoreboard can not see yet
+struct FreezeFilter {
+    freeze_time: Option<DateTime<Utc>>,
+    revealed_until: Option<SubmissionId>,
+    /// None for the full scoreboard
+    viewer: Option<UserId>,
+}
+
+impl FreezeFilter {
+    const fn is_active(&self) -> bool {
+        self.viewer.is_some() && self.freeze_time.is_some()
+    }
+
+    /// participants always see their own results
+    fn hides(&self, user_id: UserId, submission_id: SubmissionId, submitted_at: Option<DateTime<Utc>>) -> bool {
+        self.viewer.is_some_and(|viewer| viewer != user_id)
+            && self.revealed_until.is_none_or(|revealed_until| submission_id > revealed_until)
+            && self.freeze_time.zip(submitted_at).is_some_and(|(freeze_time, submitted_at)| submitted_at >= freeze_time)
+    }
+}
+
+/// sorts the rows by their total and penalty, participants with the same total and penalty share a rank
+fn rank_rows(rows: &mut [ScoreboardRow]) {
+    rows.sort_by(|a, b| b.total.cmp(&a.total).then(a.penalty.cmp(&b.penalty)).then_with(|| a.username.cmp(&b.username)));
+
+    let mut rank = 0;
+    let mut previous = None;
+    for (i, row) in rows.iter_mut().enumerate() {
+        if previous != Some((row.total, row.penalty)) {
+            rank = i + 1;
+            previous = Some((row.total, row.penalty));
+        }
+        row.rank = rank;
+    }
+}
+
+impl Database {
+    /// the scores of all participants of a contest, admins that do not participate are not shown.
+    /// with a viewer, results of other participants submitted during the freeze are pending, without one everything is shown
+    pub async fn get_scoreboard(&self, contest_id: ContestId, viewer: Option<UserId>) -> Result<Scoreboard> {
+        static PROBLEMS_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT problems.problem_id, problems.problem_name, problems.points, contest_problems.label FROM contest_problems
+            JOIN problems ON problems.problem_id = contest_problems.problem_id
+            WHERE contest_problems.contest_id = $1 ORDER BY contest_problems.position, problems.problem_id",
+        );
+        static PARTICIPANTS_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT users.user_id, users.username, contest_participations.started_at FROM contest_participations
+            JOIN users ON users.user_id = contest_participations.user_id
+            WHERE contest_participations.contest_id = $1 ORDER BY users.user_id",
+        );
+
+        let problems = PROBLEMS_QUERY
+            .execute(self, &[&contest_id])
+            .await?
+            .iter()
+            .map(|row| ScoreboardProblem {
+                problem_id: row.get(0),
+                label: row.get(3),
+                problem_name: row.get(1),
+                max_points: row.get(2),
+            })
+            .collect::<Vec<_>>();
+
+        let mut rows = PARTICIPANTS_QUERY
+            .execute(self, &[&contest_id])
+            .await?
+            .iter()
+            .map(|row| ScoreboardRow {
+                rank: 0,
+                user_id: row.get(0),
+                username: row.get(1),
+                cells: vec![ScoreboardCell::default(); problems.len()],
+                total: 0,
+                penalty: 0,
+                last_improvement: None,
+                started_at: row.get(2),
+            })
+            .collect::<Vec<_>>();
+
+        let times = self.get_contest_times(contest_id).await?;
+        let freeze = FreezeFilter {
+            freeze_time: times.freeze_time,
+            revealed_until: self.get_revealed_until(contest_id).await?,
+            viewer,
+        };
+
+        let contest_type = self.get_contest_type(contest_id).await?;
+        match contest_type {
+            ContestType::Ioi => self.fill_ioi_scoreboard(contest_id, &problems, &mut rows, &freeze).await?,
+            ContestType::Icpc => self.fill_icpc_scoreboard(contest_id, &problems, &mut rows, &freeze).await?,
+        }
+
+        rank_rows(&mut rows);
+        let frozen_since = times.freeze_time.filter(|_| freeze.is_active() && times.is_frozen(Utc::now()));
+        Ok(Scoreboard {
+            contest_type,
+            problems,
+            rows,
+            is_windowed: times.is_windowed(),
+            frozen_since,
+        })
+    }
+
+    /// ioi scores are the stored scores of the users, scores with hidden submissions are computed from the visible ones
+    async fn fill_ioi_scoreboard(&self, contest_id: ContestId, problems: &[ScoreboardProblem], rows: &mut [ScoreboardRow], freeze: &FreezeFilter) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT user_problem_scores.user_id, user_problem_scores.problem_id, user_problem_scores.score, user_problem_scores.improved_at
+            FROM user_problem_scores
+            JOIN contest_participations ON contest_participations.user_id = user_problem_scores.user_id AND contest_participations.contest_id = $1
+            JOIN contest_problems ON contest_problems.problem_id = user_problem_scores.problem_id AND contest_problems.contest_id = $1
+            WHERE user_problem_scores.contest_id = $1",
+        );
+
+        let frozen_scores = if freeze.is_active() {
+            self.get_frozen_ioi_scores(contest_id, freeze).await?
+        } else {
+            HashMap::new()
+        };
+
+        for score_row in QUERY.execute(self, &[&contest_id]).await? {
+            let (user_id, problem_id): (UserId, ProblemId) = (score_row.get(0), score_row.get(1));
+            let (Some(row), Some(index)) = (rows.iter_mut().find(|row| row.user_id == user_id), problems.iter().position(|problem| problem.problem_id == problem_id)) else {
+                continue;
+            };
+
+            let mut improved_at: Option<DateTime<Utc>> = score_row.get(3);
+            let score = match frozen_scores.get(&(user_id, problem_id)) {
+                Some(&(score, _)) => {
+                    // an improvement during the freeze would give away the hidden result
+                    improved_at = improved_at.filter(|improved_at| freeze.freeze_time.is_some_and(|freeze_time| *improved_at < freeze_time));
+                    score
+                }
+                None => score_row.get(2),
+            };
+            if let Some(cell) = row.cells.get_mut(index) {
+                cell.score = score;
+            }
+            row.total += score;
+            if score > 0 {
+                row.last_improvement = row.last_improvement.max(improved_at);
+            }
+        }
+
+        for ((user_id, problem_id), (_, pending)) in frozen_scores {
+            if let (Some(row), Some(index)) = (rows.iter_mut().find(|row| row.user_id == user_id), problems.iter().position(|problem| problem.problem_id == problem_id)) {
+                if let Some(cell) = row.cells.get_mut(index) {
+                    cell.pending = pending;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// the scores of the pairs of user and problem that have hidden submissions as (score from the visible submissions, hidden submissions)
+    async fn get_frozen_ioi_scores(&self, contest_id: ContestId, freeze: &FreezeFilter) -> Result<HashMap<(UserId, ProblemId), (i32, i32)>> {
+        // submissions without subtask results have a single row with no subtask
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT submissions.user_id, submissions.problem_id, submissions.submission_id, submissions.submitted_at, subtask_results.subtask_id, subtask_results.points
+            FROM submissions
+            JOIN contest_participations ON contest_participations.user_id = submissions.user_id AND contest_participations.contest_id = $1
+            JOIN contest_problems ON contest_problems.problem_id = submissions.problem_id AND contest_problems.contest_id = $1
+            LEFT JOIN subtask_results ON subtask_results.submission_id = submissions.submission_id
+            WHERE submissions.contest_id = $1
+            ORDER BY submissions.submission_id",
+        );
+
+        let mut hidden_submissions: HashMap<(UserId, ProblemId), Vec<SubmissionId>> = HashMap::new();
+        let mut best_points: HashMap<(UserId, ProblemId, i32), i32> = HashMap::new();
+        for row in QUERY.execute(self, &[&contest_id]).await? {
+            let (user_id, problem_id, submission_id): (UserId, ProblemId, SubmissionId) = (row.get(0), row.get(1), row.get(2));
+            if freeze.hides(user_id, submission_id, row.get(3)) {
+                let submissions = hidden_submissions.entry((user_id, problem_id)).or_default();
+                if submissions.last() != Some(&submission_id) {
+                    submissions.push(submission_id);
+                }
+            } else if let Some(subtask_id) = row.get::<Option<i32>>(4) {
+                let points = best_points.entry((user_id, problem_id, subtask_id)).or_insert(0);
+                *points = (*points).max(row.get::<Option<i32>>(5).unwrap_or(0));
+            }
+        }
+
+        let mut scores = hidden_submissions
+            .into_iter()
+            .map(|(pair, submissions)| (pair, (0, i32::try_from(submissions.len()).unwrap_or(i32::MAX))))
+            .collect::<HashMap<_, _>>();
+        for ((user_id, problem_id, _), points) in best_points {
+            if let Some((score, _)) = scores.get_mut(&(user_id, problem_id)) {
+                *score += points;
+            }
+        }
+        Ok(scores)
+    }
+
+    /// the submissions that are hidden from participants that did not make them, ordered by id
+    pub async fn get_frozen_submissions(&self, contest_id: ContestId) -> Result<Vec<FrozenSubmission>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT submissions.submission_id, users.username, problems.problem_name
+            FROM submissions
+            JOIN contests ON contests.contest_id = $1
+            JOIN contest_participations ON contest_participations.user_id = submissions.user_id AND contest_participations.contest_id = $1
+            JOIN contest_problems ON contest_problems.problem_id = submissions.problem_id AND contest_problems.contest_id = $1
+            JOIN users ON users.user_id = submissions.user_id
+            JOIN problems ON problems.problem_id = submissions.problem_id
+            WHERE submissions.contest_id = $1 AND submissions.submitted_at >= contests.freeze_time AND (contests.revealed_until IS NULL OR submissions.submission_id > contests.revealed_until)
+            ORDER BY submissions.submission_id",
+        );
+
+        Ok(QUERY
+            .execute(self, &[&contest_id])
+            .await?
+            .iter()
+            .map(|row| FrozenSubmission {
+                submission_id: row.get(0),
+                username: row.get(1),
+                problem_name: row.get(2),
+            })
+            .collect())
+    }
+
+    /// icpc results are computed from the submissions made during the contest, in windowed contests during the time of the participant. compilation errors, internal errors and submissions that are still judged do not count
+    async fn fill_icpc_scoreboard(&self, contest_id: ContestId, problems: &[ScoreboardProblem], rows: &mut [ScoreboardRow], freeze: &FreezeFilter) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT submissions.user_id, submissions.problem_id, submissions.result, submissions.submitted_at, submissions.submission_id
+            FROM submissions
+            JOIN contest_participations ON contest_participations.user_id = submissions.user_id AND contest_participations.contest_id = $1
+            JOIN contest_problems ON contest_problems.problem_id = submissions.problem_id AND contest_problems.contest_id = $1
+            WHERE submissions.contest_id = $1
+            ORDER BY submissions.submission_id",
+        );
+
+        let times = self.get_contest_times(contest_id).await?;
+        let row_indices = rows.iter().enumerate().map(|(index, row)| (row.user_id, index)).collect::<HashMap<_, _>>();
+        let mut solved_problems = vec![false; problems.len()];
+
+        for submission in QUERY.execute(self, &[&contest_id]).await? {
+            let submitted_at: Option<DateTime<Utc>> = submission.get(3);
+            let user_id: UserId = submission.get(0);
+            let (Some(row), Some(problem_index)) = (
+                row_indices.get(&user_id).and_then(|index| rows.get_mut(*index)),
+                problems.iter().position(|problem| problem.problem_id == submission.get::<ProblemId>(1)),
+            ) else {
+                continue;
+            };
+            let Some(times) = times.for_participant(row.started_at) else {
+                continue;
+            };
+            if !is_during_contest(&times, submitted_at) {
+                continue;
+            }
+            let Some(cell) = row.cells.get_mut(problem_index) else {
+                continue;
+            };
+            if cell.solve_minute.is_some() {
+                continue;
+            }
+            if freeze.hides(user_id, submission.get(4), submitted_at) {
+                cell.pending += 1;
+                continue;
+            }
+
+            match i32_to_testing_result(submission.get(2)) {
+                TestingResult::Accepted => {
+                    let solve_minute = submitted_at.zip(times.start_time).map_or(0, |(submitted_at, start_time)| (submitted_at - start_time).num_minutes());
+                    cell.score = 1;
+                    cell.solve_minute = Some(solve_minute);
+                    // submissions are ordered by id, so the first accepted submission is the first solve
+                    if let Some(solved) = solved_problems.get_mut(problem_index) {
+                        cell.is_first_solve = !*solved;
+                        *solved = true;
+                    }
+
+                    row.total += 1;
+                    row.penalty += solve_minute + ICPC_PENALTY_MINUTES * i64::from(cell.rejected_attempts);
+                    row.last_improvement = row.last_improvement.max(submitted_at);
+                }
+                // internal errors are not the fault of the participant
+                TestingResult::InQueue | TestingResult::Compiling | TestingResult::Testing | TestingResult::CompilationError | TestingResult::InternalError => {}
+                TestingResult::WrongAnswer | TestingResult::RuntimeError | TestingResult::TimeLimitExceeded | TestingResult::MemoryLimitExceeded => {
+                    cell.rejected_attempts += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// submissions from before the timestamps were stored are always counted
+fn is_during_contest(times: &ContestTimes, submitted_at: Option<DateTime<Utc>>) -> bool {
+    submitted_at.is_none_or(|submitted_at| times.has_started(submitted_at) && !times.has_ended(submitted_at))
+}
@@ -0,0 +1,180 @@
+use crate::database::storage::{Backend, Row, Storage, StorageTransaction, Value};
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use chrono::SecondsFormat;
+use rusqlite::types::{ToSqlOutput, ValueRef};
+use rusqlite::{Connection, ToSql};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::OwnedMutexGuard;
+
+impl ToSql for Value {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            Self::Null => ToSqlOutput::Owned(rusqlite::types::Value::Null),
+            Self::Integer(value) => ToSqlOutput::Owned(rusqlite::types::Value::Integer(*value)),
+            Self::Boolean(value) => ToSqlOutput::Owned(rusqlite::types::Value::Integer(i64::from(*value))),
+            Self::Text(value) => ToSqlOutput::Borrowed(ValueRef::Text(value.as_bytes())),
+            // always the same format, so that timestamps can be compared as text
+            Self::Timestamp(value) => ToSqlOutput::Owned(rusqlite::types::Value::Text(value.to_rfc3339_opts(SecondsFormat::Micros, true))),
+        })
+    }
+}
+
+/// queries are written with postgres placeholders like $1, sqlite wants them as ?1
+fn translate_query(query: &str) -> String {
+    let mut result = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '$' && chars.peek().is_some_and(char::is_ascii_digit) {
+            result.push('?');
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+fn query(connection: &Connection, query: &str, params: &[Value]) -> Result<Vec<Row>> {
+    let mut statement = connection.prepare_cached(&translate_query(query))?;
+    let column_count = statement.column_count();
+
+    let mut rows = Vec::new();
+    let mut result = statement.query(rusqlite::params_from_iter(params))?;
+    while let Some(row) = result.next()? {
+        let mut values = Vec::with_capacity(column_count);
+        for index in 0..column_count {
+            values.push(match row.get_ref(index)? {
+                ValueRef::Null => Value::Null,
+                ValueRef::Integer(value) => Value::Integer(value),
+                ValueRef::Text(value) => Value::Text(String::from_utf8_lossy(value).into_owned()),
+                ValueRef::Real(_) | ValueRef::Blob(_) => bail!("Unsupported column type in column {index}"),
+            });
+        }
+        rows.push(Row::new(values));
+    }
+
+    Ok(rows)
+}
+
+/// runs blocking sqlite calls outside of the async runtime
+async fn run<T: Send + 'static>(connection: &Arc<Mutex<Connection>>, function: impl FnOnce(&Connection) -> Result<T> + Send + 'static) -> Result<T> {
+    let connection = connection.clone();
+    tokio::task::spawn_blocking(move || {
+        let connection = connection.lock().map_err(|e| anyhow!("{e}"))?;
+        function(&connection)
+    })
+    .await?
+}
+
+/// a database stored in a single file, sqlite only allows one writer, so there is only one connection
+pub struct SqliteStorage {
+    connection: Arc<Mutex<Connection>>,
+    // held for the whole duration of a transaction, so that no other query runs in the middle of it
+    session: Arc<tokio::sync::Mutex<()>>,
+    acquire_timeout: Duration,
+    queries: Arc<AtomicU64>,
+}
+
+impl SqliteStorage {
+    /// opens the database file, it is created if it does not exist, ":memory:" creates a temporary database
+    pub fn open(path: &str, acquire_timeout: Duration) -> Result<Self> {
+        println!("opening database file \"{path}\"");
+
+        let connection = Connection::open(path)?;
+        connection.execute_batch("PRAGMA foreign_keys = ON; PRAGMA journal_mode = WAL;")?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            session: Arc::new(tokio::sync::Mutex::new(())),
+            acquire_timeout,
+            queries: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    async fn lock_session(&self) -> Result<OwnedMutexGuard<()>> {
+        tokio::time::timeout(self.acquire_timeout, self.session.clone().lock_owned())
+            .await
+            .map_err(|_elapsed| anyhow!("Timed out while waiting for the database"))
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    fn backend(&self) -> Backend {
+        Backend::Sqlite
+    }
+
+    async fn query(&self, query_string: &'static str, params: Vec<Value>) -> Result<Vec<Row>> {
+        let session = self.lock_session().await?;
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        let rows = run(&self.connection, move |connection| query(connection, query_string, &params)).await;
+        drop(session);
+        rows
+    }
+
+    async fn batch_execute(&self, sql: &str) -> Result<()> {
+        let session = self.lock_session().await?;
+        let sql = sql.to_owned();
+        let result = run(&self.connection, move |connection| Ok(connection.execute_batch(&sql)?)).await;
+        drop(session);
+        result
+    }
+
+    async fn begin(&self) -> Result<Box<dyn StorageTransaction>> {
+        let session = self.lock_session().await?;
+        // take the write lock right away, so that the transaction cannot fail halfway because the database is busy
+        run(&self.connection, |connection| Ok(connection.execute_batch("BEGIN IMMEDIATE")?)).await?;
+
+        Ok(Box::new(SqliteTransaction {
+            connection: self.connection.clone(),
+            queries: self.queries.clone(),
+            finished: false,
+            _session: session,
+        }))
+    }
+
+    fn get_query_count(&self) -> u64 {
+        self.queries.load(Ordering::Relaxed)
+    }
+}
+
+struct SqliteTransaction {
+    connection: Arc<Mutex<Connection>>,
+    queries: Arc<AtomicU64>,
+    finished: bool,
+    _session: OwnedMutexGuard<()>,
+}
+
+#[async_trait]
+impl StorageTransaction for SqliteTransaction {
+    async fn query(&mut self, query_string: &'static str, params: Vec<Value>) -> Result<Vec<Row>> {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        run(&self.connection, move |connection| query(connection, query_string, &params)).await
+    }
+
+    async fn batch_execute(&mut self, sql: &str) -> Result<()> {
+        let sql = sql.to_owned();
+        run(&self.connection, move |connection| Ok(connection.execute_batch(&sql)?)).await
+    }
+
+    async fn commit(mut self: Box<Self>) -> Result<()> {
+        run(&self.connection, |connection| Ok(connection.execute_batch("COMMIT")?)).await?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl Drop for SqliteTransaction {
+    fn drop(&mut self) {
+        // the session is only released after this, so nothing else can run before the rollback
+        if !self.finished {
+            if let Ok(connection) = self.connection.lock() {
+                if let Err(err) = connection.execute_batch("ROLLBACK") {
+                    eprintln!("Could not roll back transaction: {err}");
+                }
+            }
+        }
+    }
+}
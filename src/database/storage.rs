@@ -0,0 +1,177 @@
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// which database the data is stored in
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Postgres,
+    Sqlite,
+}
+
+/// a single value that is sent to or read from the database
+#[derive(Clone, Debug)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Boolean(bool),
+    Text(String),
+    Timestamp(DateTime<Utc>),
+}
+
+/// a type that can be passed to a query as a parameter
+pub trait SqlParam {
+    fn to_value(&self) -> Value;
+}
+
+impl SqlParam for i32 {
+    fn to_value(&self) -> Value {
+        Value::Integer(i64::from(*self))
+    }
+}
+
+impl SqlParam for i64 {
+    fn to_value(&self) -> Value {
+        Value::Integer(*self)
+    }
+}
+
+impl SqlParam for bool {
+    fn to_value(&self) -> Value {
+        Value::Boolean(*self)
+    }
+}
+
+impl SqlParam for &str {
+    fn to_value(&self) -> Value {
+        Value::Text((*self).to_owned())
+    }
+}
+
+impl SqlParam for String {
+    fn to_value(&self) -> Value {
+        Value::Text(self.clone())
+    }
+}
+
+impl SqlParam for DateTime<Utc> {
+    fn to_value(&self) -> Value {
+        Value::Timestamp(*self)
+    }
+}
+
+impl<T: SqlParam> SqlParam for Option<T> {
+    fn to_value(&self) -> Value {
+        self.as_ref().map_or(Value::Null, SqlParam::to_value)
+    }
+}
+
+/// a type that can be read from a column of a query result
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self>;
+}
+
+impl FromValue for i32 {
+    fn from_value(value: &Value) -> Result<Self> {
+        Ok(Self::try_from(i64::from_value(value)?)?)
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Integer(value) => Ok(*value),
+            value => bail!("Expected an integer, got {value:?}"),
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Boolean(value) => Ok(*value),
+            // sqlite stores booleans as integers
+            Value::Integer(value) => Ok(*value != 0),
+            value => bail!("Expected a boolean, got {value:?}"),
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Text(value) => Ok(value.clone()),
+            value => bail!("Expected text, got {value:?}"),
+        }
+    }
+}
+
+impl FromValue for DateTime<Utc> {
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Timestamp(value) => Ok(*value),
+            // sqlite stores timestamps as RFC 3339 text
+            Value::Text(value) => Ok(DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc)),
+            value => bail!("Expected a timestamp, got {value:?}"),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Null => Ok(None),
+            value => Ok(Some(T::from_value(value)?)),
+        }
+    }
+}
+
+/// a row of a query result
+pub struct Row {
+    values: Vec<Value>,
+}
+
+impl Row {
+    pub const fn new(values: Vec<Value>) -> Self {
+        Self { values }
+    }
+
+    pub fn try_get<T: FromValue>(&self, index: usize) -> Result<T> {
+        T::from_value(self.values.get(index).ok_or_else(|| anyhow!("No column with index {index}"))?)
+    }
+
+    /// queries are static, so a missing column or a wrong type is a bug in the query
+    #[allow(clippy::panic, reason = "same behaviour as tokio_postgres::Row::get")]
+    pub fn get<T: FromValue>(&self, index: usize) -> T {
+        match self.try_get(index) {
+            Ok(value) => value,
+            Err(err) => panic!("error reading column {index}: {err}"),
+        }
+    }
+}
+
+/// a database that queries can be run on, queries are always written for postgres
+#[async_trait]
+pub trait Storage: Send + Sync {
+    fn backend(&self) -> Backend;
+
+    async fn query(&self, query: &'static str, params: Vec<Value>) -> Result<Vec<Row>>;
+
+    /// executes one or more statements that are not prepared
+    async fn batch_execute(&self, sql: &str) -> Result<()>;
+
+    /// starts a transaction, it is rolled back if it is dropped without being committed
+    async fn begin(&self) -> Result<Box<dyn StorageTransaction>>;
+
+    /// the number of queries executed since the database was opened
+    fn get_query_count(&self) -> u64;
+}
+
+#[async_trait]
+pub trait StorageTransaction: Send {
+    async fn query(&mut self, query: &'static str, params: Vec<Value>) -> Result<Vec<Row>>;
+
+    async fn batch_execute(&mut self, sql: &str) -> Result<()>;
+
+    async fn commit(self: Box<Self>) -> Result<()>;
+}
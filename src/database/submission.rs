@@ -1,9 +1,11 @@
+use crate::database::contest::ContestId;
 use crate::database::problem::ProblemId;
 use crate::database::test::SubtaskId;
 use crate::database::user::UserId;
 use crate::database::{Database, DatabaseQuery};
 use crate::worker::WorkerManager;
 use anyhow::{anyhow, Result};
+use chrono::Utc;
 
 pub type SubmissionId = i32;
 
@@ -107,57 +109,150 @@ pub const fn merge_two_testing_results(a: TestingResult, b: TestingResult) -> Te
     }
 }
 
+/// everything shown on the submission page
+pub struct SubmissionView {
+    pub user_id: Option<UserId>,
+    pub contest_id: Option<ContestId>,
+    pub problem_id: ProblemId,
+    pub code: String,
+    pub result: TestingResult,
+    pub points: i32,
+    pub max_points: i32,
+    pub subtasks: Vec<SubtaskView>,
+    // the team member who submitted, None if the submission is not by a team
+    pub submitted_by: Option<String>,
+}
+
+/// a row in the list of submissions on the problem page
+pub struct SubmissionSummary {
+    pub submission_id: SubmissionId,
+    pub result: TestingResult,
+    pub points: i32,
+    pub tests_done: i32,
+    pub test_count: i64,
+}
+
+pub struct SubtaskView {
+    pub result: TestingResult,
+    pub points: i32,
+    pub max_points: i32,
+    // result and time of every test in the subtask
+    pub tests: Vec<(TestingResult, i32)>,
+}
+
 impl Database {
-    pub async fn init_submissions(&self) -> Result<()> {
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS submissions (
-                    submission_id SERIAL PRIMARY KEY,
-                    user_id INT REFERENCES users(user_id),
-                    problem_id INT REFERENCES problems(problem_id),
-                    code TEXT NOT NULL,
-                    result INT NOT NULL,
-                    points INT,
-                    tests_done INT NOT NULL
-                );",
-                &[],
-            )
-            .await?;
-        Ok(())
+    /// loads a submission with all of its subtask and test results using a fixed number of queries
+    pub async fn get_submission_view(&self, submission_id: SubmissionId) -> Result<Option<SubmissionView>> {
+        static SUBMISSION_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT submissions.code, submissions.result, submissions.points, problems.points, members.username,
+                submissions.user_id, submissions.contest_id, submissions.problem_id
+            FROM submissions
+            JOIN problems ON problems.problem_id = submissions.problem_id
+            LEFT JOIN users members ON members.user_id = submissions.submitted_by AND submissions.submitted_by <> submissions.user_id
+            WHERE submissions.submission_id = $1",
+        );
+        static SUBTASKS_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT subtask_results.subtask_id, subtask_results.result, subtask_results.points, subtasks.subtask_score
+            FROM subtask_results
+            JOIN subtasks ON subtasks.subtask_id = subtask_results.subtask_id
+            WHERE subtask_results.submission_id = $1
+            ORDER BY subtask_results.subtask_id",
+        );
+        static TESTS_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT subtask_tests.subtask_id, test_results.result, test_results.time
+            FROM subtask_results
+            JOIN subtask_tests ON subtask_tests.subtask_id = subtask_results.subtask_id
+            JOIN test_results ON test_results.test_id = subtask_tests.test_id AND test_results.submission_id = subtask_results.submission_id
+            WHERE subtask_results.submission_id = $1
+            ORDER BY subtask_tests.subtask_id, subtask_tests.test_id",
+        );
+
+        let Some(submission) = SUBMISSION_QUERY.execute(self, &[&submission_id]).await?.into_iter().next() else {
+            return Ok(None);
+        };
+
+        let mut subtask_ids = Vec::new();
+        let mut subtasks = Vec::new();
+        for row in SUBTASKS_QUERY.execute(self, &[&submission_id]).await? {
+            subtask_ids.push(row.get::<SubtaskId>(0));
+            subtasks.push(SubtaskView {
+                result: i32_to_testing_result(row.get(1)),
+                points: row.get::<Option<i32>>(2).unwrap_or(0),
+                max_points: row.get(3),
+                tests: Vec::new(),
+            });
+        }
+
+        for row in TESTS_QUERY.execute(self, &[&submission_id]).await? {
+            let subtask_id: SubtaskId = row.get(0);
+            if let Some(subtask) = subtask_ids.iter().position(|id| *id == subtask_id).and_then(|index| subtasks.get_mut(index)) {
+                subtask.tests.push((i32_to_testing_result(row.get(1)), row.get::<Option<i32>>(2).unwrap_or(0)));
+            }
+        }
+
+        Ok(Some(SubmissionView {
+            user_id: submission.get(5),
+            contest_id: submission.get(6),
+            problem_id: submission.get(7),
+            code: submission.get(0),
+            result: i32_to_testing_result(submission.get(1)),
+            points: submission.get::<Option<i32>>(2).unwrap_or(0),
+            max_points: submission.get::<i32>(3).max(1),
+            subtasks,
+            submitted_by: submission.get(4),
+        }))
     }
 
-    pub async fn add_submission(&self, user_id: UserId, problem_id: ProblemId, code: String, workers: &WorkerManager) -> Result<SubmissionId> {
-        static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO submissions (user_id, problem_id, code, result, tests_done) VALUES ($1, $2, $3, $4, $5) RETURNING submission_id");
+    /// the submission belongs to the user, `submitted_by` is the team member who sent it or the user themselves
+    pub async fn add_submission(&self, user_id: UserId, submitted_by: UserId, contest_id: ContestId, problem_id: ProblemId, code: String, workers: &WorkerManager) -> Result<SubmissionId> {
+        // the submission must only be judged once all of its rows exist
+        let submission_id = self.insert_submission(user_id, submitted_by, contest_id, problem_id, code).await?;
+        workers.queue_submission(submission_id)?;
+
+        Ok(submission_id)
+    }
+
+    async fn insert_submission(&self, user_id: UserId, submitted_by: UserId, contest_id: ContestId, problem_id: ProblemId, code: String) -> Result<SubmissionId> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "INSERT INTO submissions (user_id, submitted_by, contest_id, problem_id, code, result, tests_done, submitted_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING submission_id",
+        );
         static SUBTASK_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO subtask_results (submission_id, subtask_id, result) VALUES ($1, $2, $3)");
         static TEST_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO test_results (submission_id, test_id, result) VALUES ($1, $2, $3)");
 
+        let transaction = self.begin_transaction().await?;
+
         let submission_id = QUERY
-            .execute(self, &[&user_id, &problem_id, &code, &testing_result_to_i32(TestingResult::InQueue), &0])
+            .execute(
+                &transaction,
+                &[
+                    &user_id,
+                    &submitted_by,
+                    &contest_id,
+                    &problem_id,
+                    &code,
+                    &testing_result_to_i32(TestingResult::InQueue),
+                    &0,
+                    &Utc::now(),
+                ],
+            )
             .await?
             .first()
             .ok_or_else(|| anyhow!("No submission id returned"))?
             .get(0);
 
         // add all subtasks for the problem
-        let subtasks = self.get_subtasks_for_problem(problem_id).await?;
+        let subtasks = transaction.get_subtasks_for_problem(problem_id).await?;
         for subtask in subtasks {
-            SUBTASK_QUERY.execute(self, &[&submission_id, &subtask, &testing_result_to_i32(TestingResult::InQueue)]).await?;
+            SUBTASK_QUERY.execute(&transaction, &[&submission_id, &subtask, &testing_result_to_i32(TestingResult::InQueue)]).await?;
         }
 
         // add all tests for the problem
-        let tests = self.get_all_tests_for_problem(problem_id).await?;
+        let tests = transaction.get_all_tests_for_problem(problem_id).await?;
         for test in tests {
-            TEST_QUERY.execute(self, &[&submission_id, &test, &testing_result_to_i32(TestingResult::InQueue)]).await?;
+            TEST_QUERY.execute(&transaction, &[&submission_id, &test, &testing_result_to_i32(TestingResult::InQueue)]).await?;
         }
 
-        let database = self.clone();
-        let workers = workers.clone();
-        tokio::spawn(async move {
-            workers.test_submission(submission_id, &database).await?;
-            anyhow::Ok(())
-        });
-
-        Ok(submission_id)
+        transaction.commit().await.map(|()| submission_id)
     }
 
     async fn update_subtask_result(&self, submission_id: SubmissionId, subtask_id: SubtaskId) -> Result<()> {
@@ -195,17 +290,55 @@ impl Database {
 
         QUERY.execute(self, &[&testing_result_to_i32(result), &points, &submission_id]).await?;
 
-        let problem_id = self.get_submission_problem(submission_id).await?;
-        let user_id = self.get_user_from_submission(submission_id).await?;
-        self.update_user_score_for_problem(user_id, problem_id).await?;
+        // submissions outside of a contest, for example of a deleted contest, have no score to update
+        if let Some(contest_id) = self.get_submission_contest(submission_id).await? {
+            let problem_id = self.get_submission_problem(submission_id).await?;
+            let user_id = self.get_user_from_submission(submission_id).await?;
+            self.update_user_score_for_problem(user_id, contest_id, problem_id).await?;
+        }
 
         Ok(())
     }
 
-    pub async fn get_submissions_by_user_for_problem(&self, user_id: UserId, problem_id: ProblemId) -> Result<Vec<SubmissionId>> {
-        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT submission_id FROM submissions WHERE user_id = $1 AND problem_id = $2");
+    /// all submissions of a user for a problem in a contest, newest first
+    pub async fn get_submission_summaries_for_user_problem(&self, user_id: UserId, contest_id: ContestId, problem_id: ProblemId) -> Result<Vec<SubmissionSummary>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "SELECT submissions.submission_id, submissions.result, submissions.points, submissions.tests_done,
+                (SELECT COUNT(*) FROM test_results WHERE test_results.submission_id = submissions.submission_id)
+            FROM submissions
+            WHERE submissions.user_id = $1 AND submissions.contest_id = $2 AND submissions.problem_id = $3
+            ORDER BY submissions.submission_id DESC",
+        );
+
+        Ok(QUERY
+            .execute(self, &[&user_id, &contest_id, &problem_id])
+            .await?
+            .iter()
+            .map(|row| SubmissionSummary {
+                submission_id: row.get(0),
+                result: i32_to_testing_result(row.get(1)),
+                points: row.get::<Option<i32>>(2).unwrap_or(0),
+                tests_done: row.get(3),
+                test_count: row.get(4),
+            })
+            .collect())
+    }
+
+    pub async fn get_submissions_by_user_for_problem(&self, user_id: UserId, contest_id: ContestId, problem_id: ProblemId) -> Result<Vec<SubmissionId>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT submission_id FROM submissions WHERE user_id = $1 AND contest_id = $2 AND problem_id = $3");
 
-        Ok(QUERY.execute(self, &[&user_id, &problem_id]).await?.iter().map(|row| row.get(0)).collect())
+        Ok(QUERY.execute(self, &[&user_id, &contest_id, &problem_id]).await?.iter().map(|row| row.get(0)).collect())
+    }
+
+    pub async fn get_submission_count_by_user_for_problem(&self, user_id: UserId, contest_id: ContestId, problem_id: ProblemId) -> Result<i64> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT COUNT(*) FROM submissions WHERE user_id = $1 AND contest_id = $2 AND problem_id = $3");
+
+        Ok(QUERY
+            .execute(self, &[&user_id, &contest_id, &problem_id])
+            .await?
+            .first()
+            .ok_or_else(|| anyhow!("Could not retrieve the first row"))?
+            .get(0))
     }
 
     pub async fn get_all_submissions_for_user(&self, user_id: UserId) -> Result<Vec<SubmissionId>> {
@@ -261,11 +394,27 @@ impl Database {
             .get(0))
     }
 
-    pub async fn increment_submission_tests_done(&self, submission_id: SubmissionId) -> Result<()> {
-        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE submissions SET tests_done = tests_done + 1 WHERE submission_id = $1");
+    /// counts the tests of the submission that have a final result, so a test that is judged again is not counted twice
+    pub async fn update_submission_tests_done(&self, submission_id: SubmissionId) -> Result<i32> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new(
+            "UPDATE submissions SET tests_done = (SELECT COUNT(*) FROM test_results WHERE submission_id = $1 AND result NOT IN ($2, $3, $4))
+            WHERE submission_id = $1 RETURNING tests_done",
+        );
 
-        QUERY.execute(self, &[&submission_id]).await?;
-        Ok(())
+        Ok(QUERY
+            .execute(
+                self,
+                &[
+                    &submission_id,
+                    &testing_result_to_i32(TestingResult::InQueue),
+                    &testing_result_to_i32(TestingResult::Compiling),
+                    &testing_result_to_i32(TestingResult::Testing),
+                ],
+            )
+            .await?
+            .first()
+            .ok_or_else(|| anyhow!("No submission with id {}", submission_id))?
+            .get(0))
     }
 
     pub async fn get_submission_points(&self, submission_id: SubmissionId) -> Result<Option<i32>> {
@@ -289,6 +438,17 @@ impl Database {
             .get(0))
     }
 
+    pub async fn get_submission_contest(&self, submission_id: SubmissionId) -> Result<Option<ContestId>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT contest_id FROM submissions WHERE submission_id = $1");
+
+        Ok(QUERY
+            .execute(self, &[&submission_id])
+            .await?
+            .first()
+            .ok_or_else(|| anyhow!("No submission with id {}", submission_id))?
+            .get(0))
+    }
+
     pub async fn set_submission_result(&self, submission_id: SubmissionId, result: TestingResult) -> Result<()> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE submissions SET result = $1 WHERE submission_id = $2");
 
@@ -7,73 +7,6 @@ pub type TestId = i32;
 pub type SubtaskId = i32;
 
 impl Database {
-    pub async fn init_tests(&self) -> Result<()> {
-        // create the subtasks table
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS subtasks (
-                        subtask_id SERIAL PRIMARY KEY,
-                        problem_id INT REFERENCES problems(problem_id),
-                        subtask_score INT NOT NULL
-                    );",
-                &[],
-            )
-            .await?;
-
-        // create the tests table
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS tests (
-                        test_id SERIAL PRIMARY KEY,
-                        problem_id INT REFERENCES problems(problem_id),
-                        input_data TEXT NOT NULL,
-                        output_data TEXT NOT NULL
-                    );",
-                &[],
-            )
-            .await?;
-
-        // create a relation that connects tests to subtasks
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS subtask_tests (
-                        subtask_id INT REFERENCES subtasks(subtask_id),
-                        test_id INT REFERENCES tests(test_id),
-                        PRIMARY KEY (subtask_id, test_id)
-                    );",
-                &[],
-            )
-            .await?;
-
-        // create a table of subtask results
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS subtask_results (
-                        submission_id INT REFERENCES submissions(submission_id),
-                        subtask_id INT REFERENCES subtasks(subtask_id),
-                        points INT,
-                        result INT NOT NULL
-                    );",
-                &[],
-            )
-            .await?;
-
-        // create a table of test results
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS test_results (
-                        submission_id INT REFERENCES submissions(submission_id),
-                        test_id INT REFERENCES tests(test_id),
-                        result INT NOT NULL,
-                        time INT
-                    );",
-                &[],
-            )
-            .await?;
-
-        Ok(())
-    }
-
     pub async fn add_test(&self, input_data: &str, output_data: &str, problem_id: ProblemId) -> Result<TestId> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO tests (input_data, output_data, problem_id) VALUES ($1, $2, $3) RETURNING test_id");
 
@@ -107,6 +40,7 @@ impl Database {
 
         // increment points of the problem
         UPDATE_QUERY.execute(self, &[&problem_id, &subtask_score]).await?;
+        self.invalidate(|cache| &cache.problem_points, &problem_id)?;
 
         Ok(INSERT_QUERY
             .execute(self, &[&problem_id, &subtask_score])
@@ -137,8 +71,7 @@ impl Database {
         DELETE_SUBTASK_TESTS_QUERY.execute(self, &[&problem_id]).await?;
         DELETE_SUBTASKS_QUERY.execute(self, &[&problem_id]).await?;
         DELETE_TESTS_QUERY.execute(self, &[&problem_id]).await?;
-
-        Ok(())
+        self.reset_problem_points(problem_id).await
     }
 
     pub async fn get_all_tests_for_problem(&self, problem_id: ProblemId) -> Result<Vec<TestId>> {
@@ -243,6 +176,14 @@ impl Database {
         Ok(())
     }
 
+    /// forgets the result and the time of a test, so that it can be judged again
+    pub async fn reset_test_result(&self, submission_id: SubmissionId, test_id: TestId) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE test_results SET result = $3, time = NULL WHERE submission_id = $1 AND test_id = $2");
+
+        QUERY.execute(self, &[&submission_id, &test_id, &testing_result_to_i32(TestingResult::InQueue)]).await?;
+        Ok(())
+    }
+
     pub async fn set_subtask_result(&self, submission_id: SubmissionId, subtask_id: SubtaskId, result: TestingResult) -> Result<()> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE subtask_results SET result = $3 WHERE submission_id = $1 AND subtask_id = $2");
 
@@ -268,7 +209,14 @@ impl Database {
         DELETE_SUBTASK_TESTS_QUERY.execute(self, &[&problem_id]).await?;
         DELETE_TESTS_QUERY.execute(self, &[&problem_id]).await?;
         DELETE_SUBTASKS_QUERY.execute(self, &[&problem_id]).await?;
+        self.reset_problem_points(problem_id).await
+    }
 
-        Ok(())
+    /// the points of a problem are the sum of its subtasks, so they have to be reset together with the subtasks
+    async fn reset_problem_points(&self, problem_id: ProblemId) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE problems SET points = 0 WHERE problem_id = $1");
+
+        QUERY.execute(self, &[&problem_id]).await?;
+        self.invalidate(|cache| &cache.problem_points, &problem_id)
     }
 }
@@ -12,38 +12,16 @@ const TOKEN_LENGTH: usize = 255;
 pub type UserId = i32;
 pub type UserToken = String;
 
-impl Database {
-    pub async fn init_users(&self) -> Result<()> {
-        // create the users table
-        self.get_postgres_client()
-            .execute(
-                "CREATE TABLE IF NOT EXISTS users (
-                    user_id SERIAL PRIMARY KEY,
-                    username VARCHAR(50) UNIQUE NOT NULL,
-                    password VARCHAR(100) NOT NULL,
-                    is_admin BOOLEAN NOT NULL
-                );",
-                &[],
-            )
-            .await?;
-
-        // create the tokens table
-        self.get_postgres_client()
-            .execute(
-                &format!(
-                    "CREATE TABLE IF NOT EXISTS tokens (
-                    token VARCHAR({TOKEN_LENGTH}) NOT NULL,
-                    expiration_date TIMESTAMPTZ NOT NULL,
-                    user_id INT REFERENCES users(user_id)
-                );"
-                ),
-                &[],
-            )
-            .await?;
-
-        Ok(())
-    }
+/// a row in the list of users on the main page
+pub struct UserOverview {
+    pub user_id: UserId,
+    pub username: String,
+    pub is_admin: bool,
+    pub is_team: bool,
+    pub team_id: Option<UserId>,
+}
 
+impl Database {
     pub async fn get_user_from_username(&self, username: &str) -> Result<Option<UserId>> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT user_id FROM users WHERE username = $1");
 
@@ -61,20 +39,32 @@ impl Database {
 
         // create the user and return the user_id
         let rows = QUERY.execute(self, &[&username, &hashed_password, &is_admin]).await?;
+        let user_id = rows.first().ok_or_else(|| anyhow!("Could not retrieve the first row"))?.get(0);
+
+        // the user might have been cached as missing
+        self.invalidate(|cache| &cache.usernames, &user_id)?;
+
+        Ok(user_id)
+    }
+
+    /// a team is an account of its own, which the members share
+    pub async fn add_team(&self, username: &str, password: &str) -> Result<UserId> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE users SET is_team = TRUE WHERE user_id = $1");
 
-        Ok(rows.first().ok_or_else(|| anyhow!("Could not retrieve the first row"))?.get(0))
+        let team_id = self.add_user(username, password, false).await?;
+        QUERY.execute(self, &[&team_id]).await?;
+        Ok(team_id)
     }
 
+    /// deletes the user with everything that belongs to them, like their tokens, submissions and scores
     pub async fn delete_user(&self, user_id: UserId) -> Result<()> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("DELETE FROM users WHERE user_id = $1");
 
-        self.delete_all_tokens_for_user(user_id).await?;
-        self.remove_user_from_all_contests(user_id).await?;
-        self.delete_all_submissions_for_user(user_id).await?;
-        self.remove_user_scores(user_id).await?;
         QUERY.execute(self, &[&user_id]).await?;
-
-        Ok(())
+        self.invalidate(|cache| &cache.usernames, &user_id)?;
+        self.invalidate(|cache| &cache.user_admins, &user_id)?;
+        // the members of a deleted team are left without a team
+        self.invalidate_all(|cache| &cache.user_teams)
     }
 
     pub async fn delete_all_tokens_for_user(&self, user_id: UserId) -> Result<()> {
@@ -105,25 +95,28 @@ impl Database {
             bail!("User does not have a password");
         }
 
-        let hashed_password = hashed_password.first().ok_or_else(|| anyhow!("Error getting the hashed password"))?.get(0);
+        let hashed_password: String = hashed_password.first().ok_or_else(|| anyhow!("Error getting the hashed password"))?.get(0);
 
-        Ok(verify(password, hashed_password)?.then_some(user_id))
+        Ok(verify(password, &hashed_password)?.then_some(user_id))
     }
 
     pub async fn get_username(&self, user_id: UserId) -> Result<Option<String>> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT username FROM users WHERE user_id = $1");
 
-        let rows = QUERY.execute(self, &[&user_id]).await?;
-        if rows.is_empty() {
-            return Ok(None);
-        }
-        Ok(Some(rows.first().ok_or_else(|| anyhow!("Error getting the first column"))?.get(0)))
+        self.cached(|cache| &cache.usernames, user_id, async {
+            let rows = QUERY.execute(self, &[&user_id]).await?;
+            if rows.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(rows.first().ok_or_else(|| anyhow!("Error getting the first column"))?.get(0)))
+        })
+        .await
     }
 
     pub async fn add_token(&self, user_id: UserId) -> Result<UserToken> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO tokens (token, expiration_date, user_id) VALUES ($1, $2, $3)");
 
-        let token: UserToken = rand::thread_rng().sample_iter(&Alphanumeric).take(255).map(char::from).collect();
+        let token: UserToken = rand::thread_rng().sample_iter(&Alphanumeric).take(TOKEN_LENGTH).map(char::from).collect();
         let expiration_date = chrono::Utc::now() + TOKEN_EXPIRY;
         QUERY.execute(self, &[&token, &expiration_date, &user_id]).await?;
         Ok(token)
@@ -149,11 +142,73 @@ impl Database {
     pub async fn is_user_admin(&self, user_id: UserId) -> Result<bool> {
         static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT is_admin FROM users WHERE user_id = $1");
 
-        let rows = QUERY.execute(self, &[&user_id]).await?;
-        if rows.is_empty() {
-            bail!("User does not exist");
+        self.cached(|cache| &cache.user_admins, user_id, async {
+            let rows = QUERY.execute(self, &[&user_id]).await?;
+            if rows.is_empty() {
+                bail!("User does not exist");
+            }
+            Ok(rows.first().ok_or_else(|| anyhow!("Error getting the first column"))?.get(0))
+        })
+        .await
+    }
+
+    pub async fn is_user_team(&self, user_id: UserId) -> Result<bool> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT is_team FROM users WHERE user_id = $1");
+
+        Ok(QUERY.execute(self, &[&user_id]).await?.first().ok_or_else(|| anyhow!("User does not exist"))?.get(0))
+    }
+
+    pub async fn get_user_team(&self, user_id: UserId) -> Result<Option<UserId>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT team_id FROM users WHERE user_id = $1");
+
+        self.cached(|cache| &cache.user_teams, user_id, async {
+            Ok(QUERY.execute(self, &[&user_id]).await?.first().ok_or_else(|| anyhow!("User does not exist"))?.get(0))
+        })
+        .await
+    }
+
+    /// the user whose participations, submissions and scores are used, which is the team for team members
+    pub async fn get_acting_user(&self, user_id: UserId) -> Result<UserId> {
+        Ok(self.get_user_team(user_id).await?.unwrap_or(user_id))
+    }
+
+    /// only regular users without participations of their own can join a team, None removes them from their team
+    pub async fn set_user_team(&self, user_id: UserId, team_id: Option<UserId>) -> Result<()> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE users SET team_id = $2 WHERE user_id = $1");
+
+        if self.is_user_admin(user_id).await? || self.is_user_team(user_id).await? {
+            bail!("Only regular users can be members of a team");
         }
-        Ok(rows.first().ok_or_else(|| anyhow!("Error getting the first column"))?.get(0))
+        if let Some(team_id) = team_id {
+            if !self.is_user_team(team_id).await? {
+                bail!("User {team_id} is not a team");
+            }
+            // a member acts as the team in every contest, so their own participations could not be reached anymore
+            if !self.get_contests_for_user(user_id).await?.is_empty() {
+                bail!("User {user_id} participates in contests on their own and has to be removed from them first");
+            }
+        }
+
+        QUERY.execute(self, &[&user_id, &team_id]).await?;
+        self.invalidate(|cache| &cache.user_teams, &user_id)
+    }
+
+    /// all users ordered by id
+    pub async fn get_user_overviews(&self) -> Result<Vec<UserOverview>> {
+        static QUERY: DatabaseQuery = DatabaseQuery::new("SELECT user_id, username, is_admin, is_team, team_id FROM users ORDER BY user_id");
+
+        Ok(QUERY
+            .execute(self, &[])
+            .await?
+            .iter()
+            .map(|row| UserOverview {
+                user_id: row.get(0),
+                username: row.get(1),
+                is_admin: row.get(2),
+                is_team: row.get(3),
+                team_id: row.get(4),
+            })
+            .collect())
     }
 
     pub async fn get_all_users(&self) -> Result<Vec<UserId>> {
@@ -0,0 +1,44 @@
+use crate::database::integrity::IntegrityIssue;
+use crate::database::user::UserId;
+use crate::database::Database;
+use crate::request_handler::create_html_response;
+use crate::sidebar::{create_sidebar_context, SidebarContext};
+use anyhow::Result;
+use askama::Template;
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::Response;
+use std::net::IpAddr;
+
+#[derive(Template)]
+#[template(path = "integrity.html")]
+pub struct IntegritySite {
+    sidebar_context: SidebarContext,
+    issues: Vec<IntegrityIssue>,
+    repaired: bool,
+}
+
+/// only reports the issues, nothing is changed until the repair form is submitted
+pub async fn create_integrity_page(database: &Database, user: UserId) -> Result<Response<Full<Bytes>>> {
+    create_html_response(&IntegritySite {
+        sidebar_context: create_sidebar_context(database, Some(user)).await?,
+        issues: database.check_integrity().await?,
+        repaired: false,
+    })
+}
+
+pub async fn handle_integrity_repair(database: &Database, user: UserId, ip: IpAddr) -> Result<Response<Full<Bytes>>> {
+    let actor = database.get_audit_actor(user, ip).await?;
+    let transaction = database.begin_transaction().await?;
+    let issues = transaction.repair_integrity().await?;
+    transaction
+        .add_audit_entry(&actor, "repair_integrity", "database", &format!("{} issues", issues.len()), "0 issues")
+        .await?;
+    transaction.commit().await?;
+
+    create_html_response(&IntegritySite {
+        sidebar_context: create_sidebar_context(database, Some(user)).await?,
+        issues,
+        repaired: true,
+    })
+}
@@ -0,0 +1,40 @@
+use crate::database::cache::DatabaseStatus;
+use crate::database::user::UserId;
+use crate::database::Database;
+use crate::request_handler::create_html_response;
+use crate::sidebar::{create_sidebar_context, SidebarContext};
+use crate::worker::{JudgeStatus, WorkerManager};
+use anyhow::Result;
+use askama::Template;
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::header::CONTENT_TYPE;
+use hyper::Response;
+
+#[derive(Template)]
+#[template(path = "judge_status.html")]
+pub struct JudgeStatusSite {
+    sidebar_context: SidebarContext,
+    status: JudgeStatus,
+    database: DatabaseStatus,
+}
+
+pub async fn create_judge_status_page(database: &Database, user: UserId, workers: &WorkerManager) -> Result<Response<Full<Bytes>>> {
+    create_html_response(&JudgeStatusSite {
+        sidebar_context: create_sidebar_context(database, Some(user)).await?,
+        status: workers.get_status()?,
+        database: database.get_database_status()?,
+    })
+}
+
+pub fn create_judge_status_api_response(workers: &WorkerManager) -> Result<Response<Full<Bytes>>> {
+    let mut response = Response::new(Full::new(Bytes::from(serde_json::to_string(&workers.get_status()?)?)));
+    response.headers_mut().insert(CONTENT_TYPE, "application/json".parse()?);
+    Ok(response)
+}
+
+pub fn create_database_status_api_response(database: &Database) -> Result<Response<Full<Bytes>>> {
+    let mut response = Response::new(Full::new(Bytes::from(serde_json::to_string(&database.get_database_status()?)?)));
+    response.headers_mut().insert(CONTENT_TYPE, "application/json".parse()?);
+    Ok(response)
+}
@@ -1,8 +1,14 @@
+mod announcement;
+mod audit_log;
+mod clarification;
 mod contest;
 mod database;
+mod integrity;
+mod judge_status;
 mod main_page;
 mod problem;
 mod request_handler;
+mod scoreboard;
 mod sidebar;
 mod submission;
 mod tester;
@@ -10,15 +16,18 @@ mod tests;
 mod user;
 mod worker;
 
+use std::io::Write;
 use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
 
+use crate::database::backup::Backup;
 use crate::database::Database;
 use crate::request_handler::handle_request;
+use crate::submission::SubmissionLimits;
 use crate::tester::is_isolate_installed;
 use crate::worker::WorkerManager;
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use hyper::service::service_fn;
 use hyper_util::rt::{TokioExecutor, TokioIo};
 use tokio::net::TcpListener;
@@ -97,22 +106,49 @@ fn get_server_https_config() -> Result<ServerConfig> {
 
 #[derive(serde::Deserialize)]
 struct ConfigFile {
+    db_backend: Option<String>,
+    db_file: Option<String>,
     db_host: Option<String>,
     db_username: Option<String>,
     db_password: Option<String>,
     db_name: Option<String>,
+    db_pool_size: Option<usize>,
+    db_connect_timeout: Option<u64>,
+    db_acquire_timeout: Option<u64>,
     port: Option<u16>,
     num_workers: Option<i32>,
+    num_compile_workers: Option<i32>,
+    submission_cooldown: Option<u64>,
+    max_submissions_per_problem: Option<i32>,
+    job_timeout: Option<u64>,
 }
 
 #[derive(serde::Serialize, Clone)]
 pub struct Config {
+    /// "postgres" or "sqlite"
+    pub db_backend: String,
+    /// the file the database is stored in if the sqlite backend is used
+    pub db_file: String,
     pub db_host: String,
     pub db_username: String,
     pub db_password: String,
     pub db_name: String,
+    /// maximum number of open connections to the database
+    pub db_pool_size: usize,
+    /// number of seconds a single connection attempt to the database can take
+    pub db_connect_timeout: u64,
+    /// number of seconds a query can wait for a free connection, including reconnecting
+    pub db_acquire_timeout: u64,
     pub port: u16,
     pub num_workers: i32,
+    /// maximum number of submissions that are compiled at the same time
+    pub num_compile_workers: i32,
+    /// minimum number of seconds between two submissions of the same user
+    pub submission_cooldown: u64,
+    /// maximum number of submissions a user can make for a problem in a contest, 0 means unlimited
+    pub max_submissions_per_problem: i32,
+    /// number of seconds a worker can spend on a single test on top of its time limit before it is restarted
+    pub job_timeout: u64,
 }
 
 const CONFIG_FILE: &str = "cps_config.toml";
@@ -123,14 +159,33 @@ pub fn get_config(config_file: &str) -> Result<Config> {
     let config: ConfigFile = toml::from_str(&config_file_str)?;
 
     let config = Config {
+        db_backend: config.db_backend.unwrap_or_else(|| "postgres".to_owned()),
+        db_file: config.db_file.unwrap_or_else(|| "cps.db".to_owned()),
         db_host: config.db_host.unwrap_or_else(|| "127.0.0.1".to_owned()),
         db_username: config.db_username.unwrap_or_else(|| "postgres".to_owned()),
         db_password: config.db_password.unwrap_or_else(|| "postgres".to_owned()),
         db_name: config.db_name.unwrap_or_else(|| "cps".to_owned()),
+        db_pool_size: config.db_pool_size.unwrap_or(16),
+        db_connect_timeout: config.db_connect_timeout.unwrap_or(5),
+        db_acquire_timeout: config.db_acquire_timeout.unwrap_or(30),
         port: config.port.unwrap_or(443),
         num_workers: config.num_workers.unwrap_or(8),
+        num_compile_workers: config.num_compile_workers.unwrap_or(2),
+        submission_cooldown: config.submission_cooldown.unwrap_or(10),
+        max_submissions_per_problem: config.max_submissions_per_problem.unwrap_or(0),
+        job_timeout: config.job_timeout.unwrap_or(60),
     };
 
+    // without a compile worker no submission would ever be judged
+    if config.num_compile_workers < 1 {
+        bail!("num_compile_workers has to be at least 1, got {}", config.num_compile_workers);
+    }
+
+    // without a connection every database query would wait until it times out
+    if config.db_pool_size < 1 {
+        bail!("db_pool_size has to be at least 1, got {}", config.db_pool_size);
+    }
+
     // save the config to the file
     std::fs::write(config_file, toml::to_string(&config)?)?;
 
@@ -138,16 +193,99 @@ pub fn get_config(config_file: &str) -> Result<Config> {
 }
 
 pub async fn create_database(config: &Config) -> Result<Database> {
-    let database = Database::new(&config.db_username, &config.db_password, &config.db_host, &config.db_name).await?;
-    database.init_users().await?;
-    database.init_contests().await?;
-    database.init_problems().await?;
-    database.init_submissions().await?;
-    database.init_tests().await?;
+    let database = Database::new(config).await?;
+    database.apply_migrations().await?;
 
     Ok(database)
 }
 
+/// reports pending migrations and applies them unless this is a dry run
+pub async fn run_migrate_command(config: &Config, dry_run: bool) -> Result<()> {
+    let database = Database::new(config).await?;
+
+    println!("Current schema version: {}", database.get_schema_version().await?);
+
+    let pending = database.get_pending_migrations().await?;
+    if pending.is_empty() {
+        println!("There are no pending migrations.");
+        return Ok(());
+    }
+
+    println!("Pending migrations:");
+    for migration in &pending {
+        println!("  {}: {}", migration.version, migration.name);
+    }
+
+    if !dry_run {
+        database.apply_migrations().await?;
+        println!("Schema is now at version {}.", database.get_schema_version().await?);
+    }
+
+    Ok(())
+}
+
+/// writes the whole database into a single backup file
+pub async fn run_backup_command(config: &Config, file: &str) -> Result<()> {
+    let database = Database::new(config).await?;
+
+    let backup = database.create_backup().await?;
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(file)?);
+    backup.write(&mut writer)?;
+    writer.flush()?;
+
+    println!(
+        "Backed up {} users, {} contests, {} problems and {} submissions to \"{file}\".",
+        backup.users.len(),
+        backup.contests.len(),
+        backup.problems.len(),
+        backup.submissions.len()
+    );
+
+    Ok(())
+}
+
+/// restores a backup file into an empty database, the schema is created first if needed
+pub async fn run_restore_command(config: &Config, file: &str) -> Result<()> {
+    let backup = Backup::read(std::io::BufReader::new(std::fs::File::open(file)?))?;
+
+    let database = create_database(config).await?;
+    database.restore_backup(&backup).await?;
+
+    println!(
+        "Restored {} users, {} contests, {} problems and {} submissions from \"{file}\".",
+        backup.users.len(),
+        backup.contests.len(),
+        backup.problems.len(),
+        backup.submissions.len()
+    );
+
+    Ok(())
+}
+
+/// reports derived values that are out of date and recomputes them if repair is set
+pub async fn run_fsck_command(config: &Config, repair: bool) -> Result<()> {
+    let database = Database::new(config).await?;
+
+    let issues = if repair { database.repair_integrity().await? } else { database.check_integrity().await? };
+    if issues.is_empty() {
+        println!("No issues found.");
+        return Ok(());
+    }
+
+    println!("Found {} issues:", issues.len());
+    for issue in &issues {
+        println!("  {} of {}: stored {}, expected {}", issue.kind, issue.target, issue.stored, issue.expected);
+    }
+
+    if repair {
+        println!("All issues were repaired.");
+    } else {
+        println!("Run \"fsck --repair\" to repair them.");
+    }
+
+    Ok(())
+}
+
 pub async fn run_server(config: &Config, database: &Database) -> Result<()> {
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
     let listener = TcpListener::bind(addr).await?;
@@ -183,7 +321,8 @@ pub async fn run_server(config: &Config, database: &Database) -> Result<()> {
         database.add_user_override("admin", &password, true).await?;
     }
 
-    let workers = WorkerManager::new(config.num_workers as usize, database);
+    let workers = WorkerManager::new(config, database);
+    let submission_limits = SubmissionLimits::new(config);
 
     let server_config = get_server_https_config();
     let tls_acceptor = if let Ok(mut server_config) = server_config {
@@ -199,19 +338,17 @@ pub async fn run_server(config: &Config, database: &Database) -> Result<()> {
     println!("Server is now running on port {}.", config.port);
 
     loop {
-        let tcp_stream = listener.accept().await?.0;
+        let (tcp_stream, remote_addr) = listener.accept().await?;
         let tls_acceptor = tls_acceptor.clone();
 
         let database = database.clone();
         let workers = workers.clone();
+        let submission_limits = submission_limits.clone();
         tokio::task::spawn(async move {
-            match tcp_stream.peer_addr() {
-                Ok(addr) => println!("Got connection from: {}", addr.ip()),
-                Err(err) => println!("Error getting peer address: {err}"),
-            }
+            println!("Got connection from: {}", remote_addr.ip());
 
             let tokio_builder = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new());
-            let service = service_fn(move |request| handle_request(request, database.clone(), workers.clone()));
+            let service = service_fn(move |request| handle_request(request, remote_addr.ip(), database.clone(), workers.clone(), submission_limits.clone()));
 
             let result = if let Some(tls_acceptor) = tls_acceptor {
                 let stream = tls_acceptor.accept(tcp_stream).await;
@@ -236,9 +373,19 @@ pub async fn run_server(config: &Config, database: &Database) -> Result<()> {
 #[tokio::main]
 async fn main() -> Result<()> {
     let config = get_config(CONFIG_FILE)?;
-    let database = create_database(&config).await?;
 
-    run_server(&config, &database).await?;
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    match args.first().map(String::as_str) {
+        None => {
+            let database = create_database(&config).await?;
+            run_server(&config, &database).await?;
+        }
+        Some("migrate") => run_migrate_command(&config, args.iter().any(|arg| arg == "--dry-run")).await?,
+        Some("backup") => run_backup_command(&config, args.get(1).ok_or_else(|| anyhow!("Usage: backup <file>"))?).await?,
+        Some("restore") => run_restore_command(&config, args.get(1).ok_or_else(|| anyhow!("Usage: restore <file>"))?).await?,
+        Some("fsck") => run_fsck_command(&config, args.iter().any(|arg| arg == "--repair")).await?,
+        Some(command) => bail!("Unknown command \"{command}\", available commands are: migrate [--dry-run], backup <file>, restore <file>, fsck [--repair]"),
+    }
 
     Ok(())
 }
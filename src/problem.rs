@@ -1,3 +1,5 @@
+use crate::announcement::get_page_announcements;
+use crate::database::announcement::AnnouncementEntry;
 use crate::database::contest::ContestId;
 use crate::database::problem::ProblemId;
 use crate::database::submission::{testing_result_to_short_string, SubmissionId, TestingResult};
@@ -7,19 +9,23 @@ use crate::database::Database;
 use crate::request_handler::{create_html_response, RedirectSite};
 use crate::sidebar::{create_sidebar_context, SidebarContext};
 use crate::submission::extract_file_from_request;
+use crate::user::parse_body;
 use anyhow::{anyhow, bail, Result};
 use askama::Template;
+use chrono::Utc;
 use http_body_util::BodyExt;
 use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::{Request, Response};
 use std::collections::HashMap;
+use std::net::IpAddr;
 
 #[derive(Template)]
 #[template(path = "problem.html")]
 pub struct ProblemSite {
     contest_id: ContestId,
     problem_id: ProblemId,
+    label: String,
     problem_name: String,
     problem_description: String,
     submissions: Vec<(SubmissionId, i32, i32, bool, String)>,
@@ -27,6 +33,11 @@ pub struct ProblemSite {
     points: i32,
     max_points: i32,
     is_admin: bool,
+    submission_error: String,
+    // the statement is hidden until the contest starts
+    is_hidden: bool,
+    submissions_closed: bool,
+    announcements: Vec<AnnouncementEntry>,
 }
 
 #[derive(Template)]
@@ -40,55 +51,67 @@ pub struct EditProblemSite {
     subtasks: Vec<(SubtaskId, Vec<TestId>)>,
 }
 
-pub async fn create_problem_page(database: &Database, contest_id: &str, problem_id: &str, user_id: UserId) -> Result<Option<Response<Full<Bytes>>>> {
+/// a problem is only reachable through a contest it belongs to, and only by admins and the participants of that contest
+pub async fn can_access_problem(database: &Database, user_id: UserId, contest_id: ContestId, problem_id: ProblemId) -> Result<bool> {
+    if database.get_problem_label(contest_id, problem_id).await?.is_none() {
+        return Ok(false);
+    }
+    Ok(database.is_user_admin(user_id).await? || database.is_user_in_contest(user_id, contest_id).await?)
+}
+
+pub async fn create_problem_page(database: &Database, contest_id: &str, problem_id: &str, user_id: UserId, submission_error: String) -> Result<Option<Response<Full<Bytes>>>> {
     if let (Some(contest_id), Some(problem_id)) = (contest_id.parse::<ContestId>().ok(), problem_id.parse::<ProblemId>().ok()) {
         if !database.is_contest_id_valid(contest_id).await {
             return Ok(None);
         }
 
-        if !database.is_problem_id_valid(problem_id).await {
+        if !database.is_problem_id_valid(problem_id).await || !can_access_problem(database, user_id, contest_id, problem_id).await? {
             return Ok(None);
         }
 
-        let mut submissions = {
-            let mut res = Vec::new();
-            for id in database.get_submissions_by_user_for_problem(user_id, problem_id).await? {
-                let total_points = database.get_problem_total_points(problem_id).await?;
-                let points = database.get_submission_points(id).await?.unwrap_or(0);
-                let result = database.get_submission_result(id).await?;
-                let mut message = testing_result_to_short_string(result);
-
-                if result == TestingResult::Testing {
-                    let percent = (database.get_submission_tests_done(id).await? as f64 / database.get_tests_for_submission(id).await?.len() as f64 * 100.0).round();
-                    message = format!("{message} ({percent}%)");
-                }
+        let max_points = database.get_problem_total_points(problem_id).await?.max(1);
 
-                let hide_score = result == TestingResult::InQueue || result == TestingResult::Testing || result == TestingResult::CompilationError || result == TestingResult::Compiling;
+        let mut submissions = Vec::new();
+        for submission in database.get_submission_summaries_for_user_problem(user_id, contest_id, problem_id).await? {
+            let result = submission.result;
+            let mut message = testing_result_to_short_string(result);
 
-                res.push((id, points, total_points.max(1), hide_score, message));
+            if result == TestingResult::Testing {
+                let percent = (f64::from(submission.tests_done) / submission.test_count as f64 * 100.0).round();
+                message = format!("{message} ({percent}%)");
             }
-            res
-        };
 
-        // Sort by submission id in descending order
-        submissions.sort_by(|a, b| b.0.cmp(&a.0));
+            let hide_score = result == TestingResult::InQueue || result == TestingResult::Testing || result == TestingResult::CompilationError || result == TestingResult::Compiling;
 
-        let problem_description = database.get_problem_description(problem_id).await?;
+            submissions.push((submission.submission_id, submission.points, max_points, hide_score, message));
+        }
 
-        let points = database.get_user_score_for_problem(user_id, problem_id).await?;
-        let max_points = database.get_problem_total_points(problem_id).await?.max(1);
+        let points = database.get_user_score_for_problem(user_id, contest_id, problem_id).await?;
         let is_admin = database.is_user_admin(user_id).await?;
 
+        let user_times = database.get_user_contest_times(user_id, contest_id).await?;
+        let is_hidden = !is_admin && !user_times.is_some_and(|times| times.has_started(Utc::now()));
+        let submissions_closed = !is_admin && !user_times.is_some_and(|times| times.is_running(Utc::now()));
+        let problem_description = if is_hidden { String::new() } else { database.get_problem_description(problem_id).await? };
+
         return Ok(Some(create_html_response(&ProblemSite {
             contest_id,
             problem_id,
             problem_description,
+            label: database
+                .get_problem_label(contest_id, problem_id)
+                .await?
+                .ok_or_else(|| anyhow!("The problem is not part of the contest"))?,
             problem_name: database.get_problem_name(problem_id).await?,
             submissions,
             sidebar_context: create_sidebar_context(database, Some(user_id)).await?,
             points,
             max_points,
             is_admin,
+            submission_error,
+            is_hidden,
+            submissions_closed,
+            announcements: get_page_announcements(database, contest_id, Some(problem_id), user_id, is_hidden).await?,
         })?));
     }
 
@@ -168,7 +191,26 @@ pub fn parse_body_with_boundaries(body: &str) -> Result<HashMap<String, String>>
     Ok(result)
 }
 
-pub async fn handle_problem_editing(database: &Database, contest_id: &str, problem_id: &str, user_id: UserId, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+/// a short description of a problem statement, used for the audit log
+async fn get_statement_summary(database: &Database, problem_id: ProblemId) -> Result<String> {
+    Ok(format!(
+        "name: {}, description: {} characters",
+        database.get_problem_name(problem_id).await?,
+        database.get_problem_description(problem_id).await?.chars().count()
+    ))
+}
+
+/// a short description of the test data of a problem, used for the audit log
+async fn get_tests_summary(database: &Database, problem_id: ProblemId) -> Result<String> {
+    Ok(format!(
+        "{} subtasks, {} tests, {} points",
+        database.get_subtasks_for_problem(problem_id).await?.len(),
+        database.get_all_tests_for_problem(problem_id).await?.len(),
+        database.get_problem_total_points(problem_id).await?
+    ))
+}
+
+pub async fn handle_problem_editing(database: &Database, contest_id: &str, problem_id: &str, user_id: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
     if let (Some(contest_id), Some(problem_id)) = (contest_id.parse::<ContestId>().ok(), problem_id.parse::<ProblemId>().ok()) {
         if !database.is_contest_id_valid(contest_id).await {
             return Ok(None);
@@ -186,14 +228,22 @@ pub async fn handle_problem_editing(database: &Database, contest_id: &str, probl
         let body = String::from_utf8_lossy(&body).to_string();
         let parsed_body = parse_body_with_boundaries(&body)?;
 
+        let actor = database.get_audit_actor(user_id, ip).await?;
+        let transaction = database.begin_transaction().await?;
+        let before = get_statement_summary(&transaction, problem_id).await?;
+
         if let Some(description) = parsed_body.get("description") {
-            database.set_problem_description(problem_id, description).await?;
+            transaction.set_problem_description(problem_id, description).await?;
         }
 
         if let Some(name) = parsed_body.get("name") {
-            database.set_problem_name(problem_id, name).await?;
+            transaction.set_problem_name(problem_id, name).await?;
         }
 
+        let after = get_statement_summary(&transaction, problem_id).await?;
+        transaction.add_audit_entry(&actor, "edit_problem", &format!("problem {problem_id}"), &before, &after).await?;
+        transaction.commit().await?;
+
         return Ok(Some(create_html_response(&RedirectSite {
             url: format!("/contest/{contest_id}/edit_problem/{problem_id}"),
         })?));
@@ -202,7 +252,41 @@ pub async fn handle_problem_editing(database: &Database, contest_id: &str, probl
     Ok(None)
 }
 
-pub async fn create_new_problem(database: &Database, contest_id: &str) -> Result<Response<Full<Bytes>>> {
+/// deletes the problem entirely, the form has to be submitted with the confirmation checkbox ticked
+pub async fn handle_problem_deletion(database: &Database, contest_id: &str, problem_id: &str, user_id: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    if let (Some(contest_id), Some(problem_id)) = (contest_id.parse::<ContestId>().ok(), problem_id.parse::<ProblemId>().ok()) {
+        if !database.is_contest_id_valid(contest_id).await || !database.is_problem_id_valid(problem_id).await {
+            return Ok(None);
+        }
+
+        if !database.is_user_admin(user_id).await? {
+            return Ok(None);
+        }
+
+        let body = request.into_body().collect().await?.to_bytes();
+        let body = String::from_utf8_lossy(&body).to_string();
+        if parse_body(&body).get("confirm").map(String::as_str) != Some("on") {
+            return Ok(Some(create_html_response(&RedirectSite {
+                url: format!("/contest/{contest_id}/edit_problem/{problem_id}"),
+            })?));
+        }
+
+        let actor = database.get_audit_actor(user_id, ip).await?;
+        let transaction = database.begin_transaction().await?;
+        let before = format!("{}, {}", get_statement_summary(&transaction, problem_id).await?, get_tests_summary(&transaction, problem_id).await?);
+        transaction.remove_problem(problem_id).await?;
+        transaction.add_audit_entry(&actor, "delete_problem", &format!("problem {problem_id}"), &before, "").await?;
+        transaction.commit().await?;
+
+        return Ok(Some(create_html_response(&RedirectSite {
+            url: format!("/contest/{contest_id}"),
+        })?));
+    }
+
+    Ok(None)
+}
+
+pub async fn create_new_problem(database: &Database, contest_id: &str, user_id: UserId, ip: IpAddr) -> Result<Response<Full<Bytes>>> {
     if let Ok(contest_id) = contest_id.parse::<ContestId>() {
         if !database.is_contest_id_valid(contest_id).await {
             bail!("Invalid contest id");
@@ -216,9 +300,15 @@ pub async fn create_new_problem(database: &Database, contest_id: &str) -> Result
             }
         }
 
-        let problem_id = database.add_problem_override(&format!("Problem {problem_number}"), "Insert description here...", 1000).await?;
+        let actor = database.get_audit_actor(user_id, ip).await?;
+        let transaction = database.begin_transaction().await?;
+        let problem_id = transaction.add_problem_override(&format!("Problem {problem_number}"), "Insert description here...", 1000).await?;
 
-        database.add_problem_to_contest(contest_id, problem_id).await?;
+        transaction.add_problem_to_contest(contest_id, problem_id).await?;
+        transaction
+            .add_audit_entry(&actor, "add_problem", &format!("contest {contest_id}"), "", &format!("problem {problem_id}"))
+            .await?;
+        transaction.commit().await?;
 
         return create_html_response(&RedirectSite {
             url: format!("/contest/{contest_id}/edit_problem/{problem_id}"),
@@ -235,7 +325,7 @@ pub struct CPSTests {
     pub subtask_points: Vec<i32>,
 }
 
-pub async fn handle_tests_uploading(database: &Database, contest_id: &str, problem_id: &str, request: Request<Incoming>) -> Result<Response<Full<Bytes>>> {
+pub async fn handle_tests_uploading(database: &Database, contest_id: &str, problem_id: &str, user_id: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Response<Full<Bytes>>> {
     let contest_id = contest_id.parse::<ContestId>().map_err(|_e| anyhow!("Invalid contest id"))?;
     let problem_id = problem_id.parse::<ProblemId>().map_err(|_e| anyhow!("Invalid problem id"))?;
 
@@ -252,24 +342,39 @@ pub async fn handle_tests_uploading(database: &Database, contest_id: &str, probl
 
     let tests: CPSTests = bincode::deserialize(decompressed.as_slice())?;
 
-    database.remove_all_submissions_testing_data_for_problem(problem_id).await?;
-    database.remove_all_test_data_for_problem(problem_id).await?;
+    let actor = database.get_audit_actor(user_id, ip).await?;
+    let transaction = database.begin_transaction().await?;
+    let before = get_tests_summary(&transaction, problem_id).await?;
+    replace_tests(&transaction, problem_id, tests).await?;
+    let after = get_tests_summary(&transaction, problem_id).await?;
+    transaction.add_audit_entry(&actor, "upload_tests", &format!("problem {problem_id}"), &before, &after).await?;
+    transaction.commit().await?;
+
+    create_html_response(&RedirectSite {
+        url: format!("/contest/{contest_id}/edit_problem/{problem_id}"),
+    })
+}
+
+/// replaces all tests of a problem, if anything fails, the old tests are kept
+async fn replace_tests(database: &Database, problem_id: ProblemId, tests: CPSTests) -> Result<()> {
+    let transaction = database.begin_transaction().await?;
+
+    transaction.remove_all_submissions_testing_data_for_problem(problem_id).await?;
+    transaction.remove_all_test_data_for_problem(problem_id).await?;
 
     let mut db_tests = Vec::new();
 
     for (input, output) in tests.tests {
-        let test_id = database.add_test(&input, &output, problem_id).await?;
+        let test_id = transaction.add_test(&input, &output, problem_id).await?;
         db_tests.push(test_id);
     }
 
     for (tests, points) in tests.subtask_tests.into_iter().zip(tests.subtask_points.into_iter()) {
-        let subtask_id = database.add_subtask(problem_id, points).await?;
+        let subtask_id = transaction.add_subtask(problem_id, points).await?;
         for test in tests {
-            database.add_test_to_subtask(subtask_id, *db_tests.get(test).ok_or_else(|| anyhow!("Invalid test index"))?).await?;
+            transaction.add_test_to_subtask(subtask_id, *db_tests.get(test).ok_or_else(|| anyhow!("Invalid test index"))?).await?;
         }
     }
 
-    create_html_response(&RedirectSite {
-        url: format!("/contest/{contest_id}/edit_problem/{problem_id}"),
-    })
+    transaction.commit().await
 }
@@ -1,15 +1,25 @@
-use crate::contest::{create_contest_page, handle_participant_modification, handle_problem_deletion_from_contest};
+use crate::announcement::handle_announcement_posting;
+use crate::audit_log::create_audit_log_page;
+use crate::clarification::{create_clarifications_page, handle_clarification_answer, handle_clarification_question};
+use crate::contest::{
+    create_contest_page, handle_contest_creation, handle_contest_deletion, handle_contest_extension, handle_contest_renaming, handle_contest_start, handle_contest_times_modification,
+    handle_contest_type_modification, handle_participant_modification, handle_problem_deletion_from_contest, handle_problem_labeling, handle_problem_move,
+};
 use crate::database::Database;
+use crate::integrity::{create_integrity_page, handle_integrity_repair};
+use crate::judge_status::{create_database_status_api_response, create_judge_status_api_response, create_judge_status_page};
 use crate::main_page::create_main_page;
-use crate::problem::{create_edit_problem_page, create_new_problem, create_problem_page, handle_problem_editing, handle_tests_uploading};
-use crate::submission::{create_submission_page, handle_submission_form};
-use crate::user::{create_login_page, delete_user, get_login_token, handle_login_form, handle_logout_form, handle_user_creation, LoginSite};
+use crate::problem::{create_edit_problem_page, create_new_problem, create_problem_page, handle_problem_deletion, handle_problem_editing, handle_tests_uploading};
+use crate::scoreboard::{create_reveal_page, create_scoreboard_page, handle_reveal};
+use crate::submission::{create_submission_page, handle_submission_form, SubmissionLimits};
+use crate::user::{create_login_page, get_login_token, handle_login_form, handle_logout_form, handle_team_assignment, handle_user_creation, handle_user_deletion, LoginSite};
 use crate::worker::WorkerManager;
 use anyhow::Result;
 use askama::Template;
 use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::{Request, Response};
+use std::net::IpAddr;
 
 pub fn create_raw_response(data: Bytes) -> Response<Full<Bytes>> {
     Response::new(Full::new(data))
@@ -29,8 +39,9 @@ pub struct RedirectSite {
 #[template(path = "not_found.html")]
 pub struct NotFoundSite;
 
-pub async fn handle_request(request: Request<Incoming>, database: Database, workers: WorkerManager) -> Result<Response<Full<Bytes>>> {
-    let res = handle_request_inner(request, database, workers).await;
+/// ip is the address of the client, it is recorded for administrative actions
+pub async fn handle_request(request: Request<Incoming>, ip: IpAddr, database: Database, workers: WorkerManager, submission_limits: SubmissionLimits) -> Result<Response<Full<Bytes>>> {
+    let res = handle_request_inner(request, ip, database, workers, submission_limits).await;
     if let Err(err) = &res {
         eprintln!("Error: {err}");
     }
@@ -38,9 +49,11 @@ pub async fn handle_request(request: Request<Incoming>, database: Database, work
 }
 
 #[allow(clippy::too_many_lines)]
-async fn handle_request_inner(request: Request<Incoming>, database: Database, workers: WorkerManager) -> Result<Response<Full<Bytes>>> {
+async fn handle_request_inner(request: Request<Incoming>, ip: IpAddr, database: Database, workers: WorkerManager, submission_limits: SubmissionLimits) -> Result<Response<Full<Bytes>>> {
     let token = get_login_token(&request)?;
-    let user = if let Some(token) = &token { database.get_user_from_token(token.clone()).await? } else { None };
+    let member = if let Some(token) = &token { database.get_user_from_token(token.clone()).await? } else { None };
+    // team members act as their team everywhere, only their submissions remember who submitted
+    let user = if let Some(member) = member { Some(database.get_acting_user(member).await?) } else { None };
     let is_admin = if let Some(user) = user { database.is_user_admin(user).await? } else { false };
 
     let mut parts_owned = request.uri().path().split('/').map(ToOwned::to_owned).collect::<Vec<String>>();
@@ -58,29 +71,135 @@ async fn handle_request_inner(request: Request<Incoming>, database: Database, wo
             }
 
             if parts == ["create_user"] {
-                return handle_user_creation(&database, request, is_admin).await;
+                return handle_user_creation(&database, request, user, ip, is_admin).await;
             }
 
-            if parts.len() == 5 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"problem" && parts.get(4).unwrap_or(&"") == &"submit_file" {
-                return handle_submission_form(&database, user, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), request, &workers)
+            if is_admin && parts.len() == 2 && parts.first().unwrap_or(&"") == &"delete_user" {
+                return handle_user_deletion(&database, parts.get(1).unwrap_or(&""), user, ip, request).await;
+            }
+
+            if is_admin && parts.len() == 2 && parts.first().unwrap_or(&"") == &"set_team" {
+                return handle_team_assignment(&database, parts.get(1).unwrap_or(&""), user, ip, request).await;
+            }
+
+            if parts == ["create_contest"] && is_admin {
+                return handle_contest_creation(&database, user, ip, request).await;
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"rename" && is_admin {
+                return handle_contest_renaming(&database, parts.get(1).unwrap_or(&""), user, ip, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"set_times" && is_admin {
+                return handle_contest_times_modification(&database, parts.get(1).unwrap_or(&""), user, ip, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"set_type" && is_admin {
+                return handle_contest_type_modification(&database, parts.get(1).unwrap_or(&""), user, ip, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"extend" && is_admin {
+                return handle_contest_extension(&database, parts.get(1).unwrap_or(&""), user, ip, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"start" {
+                return handle_contest_start(&database, parts.get(1).unwrap_or(&""), user)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"announce" && is_admin {
+                return handle_announcement_posting(&database, parts.get(1).unwrap_or(&""), user, ip, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"ask" {
+                return handle_clarification_question(&database, parts.get(1).unwrap_or(&""), user, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 4 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"answer" && is_admin {
+                return handle_clarification_answer(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), user, ip, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"reveal" && is_admin {
+                return handle_reveal(&database, parts.get(1).unwrap_or(&""), user, ip, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 4 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"move_problem" && is_admin {
+                return handle_problem_move(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), user, ip, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"label_problems" && is_admin {
+                return handle_problem_labeling(&database, parts.get(1).unwrap_or(&""), user, ip, request)
                     .await?
                     .map_or_else(|| create_html_response(&NotFoundSite), Ok);
             }
 
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"delete" && is_admin {
+                return handle_contest_deletion(&database, parts.get(1).unwrap_or(&""), user, ip, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if parts.len() == 5 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"problem" && parts.get(4).unwrap_or(&"") == &"submit_file" {
+                return handle_submission_form(
+                    &database,
+                    member.unwrap_or(user),
+                    parts.get(1).unwrap_or(&""),
+                    parts.get(3).unwrap_or(&""),
+                    request,
+                    &workers,
+                    &submission_limits,
+                )
+                .await?
+                .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
             if parts.len() == 2 && parts.first().unwrap_or(&"") == &"modify_participants" {
-                return handle_participant_modification(&database, parts.get(1).unwrap_or(&""), user, request)
+                return handle_participant_modification(&database, parts.get(1).unwrap_or(&""), user, ip, request)
                     .await?
                     .map_or_else(|| create_html_response(&NotFoundSite), Ok);
             }
 
             if parts.len() == 4 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"edit_problem" {
-                return handle_problem_editing(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), user, request)
+                return handle_problem_editing(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), user, ip, request)
                     .await?
                     .map_or_else(|| create_html_response(&NotFoundSite), Ok);
             }
 
+            if parts.len() == 4 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"remove_problem" && is_admin {
+                return handle_problem_deletion_from_contest(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), user, ip, request).await;
+            }
+
+            if parts.len() == 4 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"delete_problem" {
+                return handle_problem_deletion(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), user, ip, request)
+                    .await?
+                    .map_or_else(|| create_html_response(&NotFoundSite), Ok);
+            }
+
+            if is_admin && parts == ["integrity"] {
+                return handle_integrity_repair(&database, user, ip).await;
+            }
+
             if parts.len() == 4 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"upload_tests" && is_admin {
-                return handle_tests_uploading(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), request).await;
+                return handle_tests_uploading(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), user, ip, request).await;
             }
         } else {
             return create_html_response(&LoginSite {
@@ -103,6 +222,12 @@ async fn handle_request_inner(request: Request<Incoming>, database: Database, wo
                 "submission.css" => Some(include_bytes!("../templates/css/submission.css").to_vec()),
                 "login.css" => Some(include_bytes!("../templates/css/login.css").to_vec()),
                 "edit_problem.css" => Some(include_bytes!("../templates/css/edit_problem.css").to_vec()),
+                "judge_status.css" => Some(include_bytes!("../templates/css/judge_status.css").to_vec()),
+                "audit_log.css" => Some(include_bytes!("../templates/css/audit_log.css").to_vec()),
+                "integrity.css" => Some(include_bytes!("../templates/css/integrity.css").to_vec()),
+                "scoreboard.css" => Some(include_bytes!("../templates/css/scoreboard.css").to_vec()),
+                "announcements.css" => Some(include_bytes!("../templates/css/announcements.css").to_vec()),
+                "clarifications.css" => Some(include_bytes!("../templates/css/clarifications.css").to_vec()),
                 _ => None,
             };
 
@@ -127,8 +252,26 @@ async fn handle_request_inner(request: Request<Incoming>, database: Database, wo
                 }
             }
 
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"scoreboard" {
+                if let Some(result) = create_scoreboard_page(&database, parts.get(1).unwrap_or(&""), user).await? {
+                    return Ok(result);
+                }
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"clarifications" {
+                if let Some(result) = create_clarifications_page(&database, parts.get(1).unwrap_or(&""), user, &request).await? {
+                    return Ok(result);
+                }
+            }
+
+            if parts.len() == 3 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"reveal" && is_admin {
+                if let Some(result) = create_reveal_page(&database, parts.get(1).unwrap_or(&""), user).await? {
+                    return Ok(result);
+                }
+            }
+
             if parts.len() == 4 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"problem" {
-                if let Some(result) = create_problem_page(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), user).await? {
+                if let Some(result) = create_problem_page(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), user, String::new()).await? {
                     return Ok(result);
                 }
             }
@@ -139,12 +282,8 @@ async fn handle_request_inner(request: Request<Incoming>, database: Database, wo
                 }
             }
 
-            if parts.len() == 4 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"delete_problem" && is_admin {
-                return handle_problem_deletion_from_contest(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&"")).await;
-            }
-
             if parts.len() == 6 && parts.first().unwrap_or(&"") == &"contest" && parts.get(2).unwrap_or(&"") == &"problem" && parts.get(4).unwrap_or(&"") == &"submission" {
-                if let Some(result) = create_submission_page(&database, parts.get(5).unwrap_or(&""), user).await? {
+                if let Some(result) = create_submission_page(&database, parts.get(1).unwrap_or(&""), parts.get(3).unwrap_or(&""), parts.get(5).unwrap_or(&""), user).await? {
                     return Ok(result);
                 }
             }
@@ -161,11 +300,27 @@ async fn handle_request_inner(request: Request<Incoming>, database: Database, wo
             }
 
             if is_admin && parts.len() == 2 && parts.first().unwrap_or(&"") == &"add_problem" {
-                return create_new_problem(&database, parts.get(1).unwrap_or(&"")).await;
+                return create_new_problem(&database, parts.get(1).unwrap_or(&""), user, ip).await;
             }
 
-            if is_admin && parts.len() == 2 && parts.first().unwrap_or(&"") == &"delete_user" {
-                return delete_user(&database, parts.get(1).unwrap_or(&"")).await;
+            if is_admin && parts == ["audit_log"] {
+                return create_audit_log_page(&database, user, &request).await;
+            }
+
+            if is_admin && parts == ["integrity"] {
+                return create_integrity_page(&database, user).await;
+            }
+
+            if is_admin && parts == ["judge_status"] {
+                return create_judge_status_page(&database, user, &workers).await;
+            }
+
+            if is_admin && parts == ["api", "judge_status"] {
+                return create_judge_status_api_response(&workers);
+            }
+
+            if is_admin && parts == ["api", "database_status"] {
+                return create_database_status_api_response(&database);
             }
         } else {
             return create_html_response(&LoginSite {
@@ -0,0 +1,134 @@
+use crate::database::contest::{ContestId, ContestType};
+use crate::database::scoreboard::{FrozenSubmission, Scoreboard, ICPC_PENALTY_MINUTES};
+use crate::database::user::UserId;
+use crate::database::Database;
+use crate::request_handler::{create_html_response, RedirectSite};
+use crate::sidebar::{create_sidebar_context, SidebarContext};
+use crate::user::parse_body;
+use anyhow::{bail, Result};
+use askama::Template;
+use chrono::Utc;
+use http_body_util::BodyExt;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::{Request, Response};
+use std::net::IpAddr;
+
+#[derive(Template)]
+#[template(path = "scoreboard.html")]
+pub struct ScoreboardSite {
+    sidebar_context: SidebarContext,
+    contest_id: ContestId,
+    contest_name: String,
+    scoreboard: Scoreboard,
+    is_icpc: bool,
+    penalty_minutes: i64,
+    user: UserId,
+    // the page reloads itself while the contest is running
+    is_live: bool,
+}
+
+#[derive(Template)]
+#[template(path = "reveal.html")]
+pub struct RevealSite {
+    sidebar_context: SidebarContext,
+    contest_id: ContestId,
+    contest_name: String,
+    scoreboard: Scoreboard,
+    is_icpc: bool,
+    user: UserId,
+    has_ended: bool,
+    frozen_submissions: Vec<FrozenSubmission>,
+}
+
+/// only participants of the contest and admins can see the scoreboard, admins always see all results
+pub async fn create_scoreboard_page(database: &Database, contest_id: &str, user: UserId) -> Result<Option<Response<Full<Bytes>>>> {
+    let Ok(contest_id) = contest_id.parse::<ContestId>() else {
+        return Ok(None);
+    };
+    let is_admin = database.is_user_admin(user).await?;
+    if !database.is_contest_id_valid(contest_id).await || !(is_admin || database.is_user_in_contest(user, contest_id).await?) {
+        return Ok(None);
+    }
+
+    let times = database.get_contest_times(contest_id).await?;
+    let scoreboard = database.get_scoreboard(contest_id, if is_admin { None } else { Some(user) }).await?;
+
+    Ok(Some(create_html_response(&ScoreboardSite {
+        sidebar_context: create_sidebar_context(database, Some(user)).await?,
+        contest_id,
+        contest_name: database.get_contest_name(contest_id).await?,
+        is_icpc: scoreboard.contest_type == ContestType::Icpc,
+        penalty_minutes: ICPC_PENALTY_MINUTES,
+        scoreboard,
+        user,
+        is_live: !times.has_ended(Utc::now()),
+    })?))
+}
+
+/// the scoreboard as the participants see it, with the submissions that are still hidden by the freeze
+pub async fn create_reveal_page(database: &Database, contest_id: &str, user: UserId) -> Result<Option<Response<Full<Bytes>>>> {
+    let Ok(contest_id) = contest_id.parse::<ContestId>() else {
+        return Ok(None);
+    };
+    if !database.is_contest_id_valid(contest_id).await {
+        return Ok(None);
+    }
+
+    let times = database.get_contest_times(contest_id).await?;
+    let scoreboard = database.get_scoreboard(contest_id, Some(user)).await?;
+
+    Ok(Some(create_html_response(&RevealSite {
+        sidebar_context: create_sidebar_context(database, Some(user)).await?,
+        contest_id,
+        contest_name: database.get_contest_name(contest_id).await?,
+        is_icpc: scoreboard.contest_type == ContestType::Icpc,
+        scoreboard,
+        user,
+        has_ended: times.has_ended(Utc::now()),
+        frozen_submissions: database.get_frozen_submissions(contest_id).await?,
+    })?))
+}
+
+/// reveals the next frozen submission, or all of them, once the contest has ended
+pub async fn handle_reveal(database: &Database, contest_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Option<Response<Full<Bytes>>>> {
+    let Ok(contest_id) = contest_id.parse::<ContestId>() else {
+        return Ok(None);
+    };
+    if !database.is_contest_id_valid(contest_id).await {
+        return Ok(None);
+    }
+    if !database.get_contest_times(contest_id).await?.has_ended(Utc::now()) {
+        bail!("The scoreboard can only be revealed after the contest has ended");
+    }
+
+    let body = request.into_body().collect().await?.to_bytes();
+    let body = String::from_utf8_lossy(&body).to_string();
+    let frozen_submissions = database.get_frozen_submissions(contest_id).await?;
+    let revealed = match parse_body(&body).get("step").map(String::as_str) {
+        Some("next") => frozen_submissions.first(),
+        Some("all") => frozen_submissions.last(),
+        _ => bail!("Invalid reveal step"),
+    };
+
+    if let Some(revealed) = revealed {
+        let actor = database.get_audit_actor(user, ip).await?;
+        let transaction = database.begin_transaction().await?;
+        let before = transaction.get_revealed_until(contest_id).await?;
+        transaction.set_revealed_until(contest_id, Some(revealed.submission_id)).await?;
+        transaction
+            .add_audit_entry(
+                &actor,
+                "reveal_scoreboard",
+                &format!("contest {contest_id}"),
+                &before.map(|submission_id| format!("until submission {submission_id}")).unwrap_or_default(),
+                &format!("until submission {}", revealed.submission_id),
+            )
+            .await?;
+        transaction.commit().await?;
+    }
+
+    Ok(Some(create_html_response(&RedirectSite {
+        url: format!("/contest/{contest_id}/reveal"),
+    })?))
+}
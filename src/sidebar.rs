@@ -1,35 +1,55 @@
+use crate::contest::{get_user_countdown, ContestCountdown};
 use crate::database::contest::ContestId;
 use crate::database::problem::ProblemId;
 use crate::database::user::UserId;
 use crate::database::Database;
 use anyhow::Result;
+use chrono::Utc;
 
 #[allow(clippy::type_complexity)]
 pub struct SidebarContext {
     pub logged_in: bool,
     pub username: String,
-    pub contests: Vec<(ContestId, String, i32, i32, Vec<(ProblemId, String, i32, i32)>)>,
+    // the last value is the number of clarification answers the user has not seen yet
+    pub contests: Vec<(ContestId, String, i32, i32, Vec<(ProblemId, String, String, i32, i32)>, Option<ContestCountdown>, i64)>,
 }
 
 pub async fn create_sidebar_context(database: &Database, user: Option<UserId>) -> Result<SidebarContext> {
     let mut contests = Vec::new();
     if let Some(user) = user {
-        for id in database.get_contests_for_user(user).await? {
+        let is_admin = database.is_user_admin(user).await?;
+        let now = Utc::now();
+        let new_answer_counts = database.get_new_answer_counts(user).await?;
+        for (id, name, mut problems) in database.get_contest_overviews_for_user(user).await? {
+            let times = database.get_contest_times(id).await?;
+            let user_times = database.get_user_contest_times(user, id).await?;
+            // the problems are hidden until the contest starts, in windowed contests until the user starts
+            if !is_admin && !user_times.is_some_and(|user_times| user_times.has_started(now)) {
+                problems.clear();
+            }
+
             let mut contest_points = 0;
             let mut contest_max_points = 0;
 
-            let mut problems = Vec::new();
-            for problem in database.get_problems_for_contest(id).await? {
-                let max_points = database.get_problem_total_points(problem).await?.max(1);
-                let points = database.get_user_score_for_problem(user, problem).await?;
+            let mut problem_vec = Vec::new();
+            for (problem, label, problem_name, points, max_points) in problems {
+                let max_points = max_points.max(1);
 
                 contest_points += points;
                 contest_max_points += max_points;
 
-                problems.push((problem, database.get_problem_name(problem).await?, points, max_points));
+                problem_vec.push((problem, label, problem_name, points, max_points));
             }
 
-            contests.push((id, database.get_contest_name(id).await?, contest_points, contest_max_points.max(1), problems));
+            contests.push((
+                id,
+                name,
+                contest_points,
+                contest_max_points.max(1),
+                problem_vec,
+                get_user_countdown(&times, user_times.as_ref(), now),
+                new_answer_counts.get(&id).copied().unwrap_or(0),
+            ));
         }
     }
 
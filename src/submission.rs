@@ -1,15 +1,24 @@
+use crate::database::contest::ContestId;
+use crate::database::problem::ProblemId;
 use crate::database::submission::{testing_result_to_short_string, testing_result_to_string, TestingResult};
 use crate::database::user::UserId;
 use crate::database::Database;
+use crate::problem::{can_access_problem, create_problem_page};
 use crate::request_handler::{create_html_response, RedirectSite};
 use crate::sidebar::{create_sidebar_context, SidebarContext};
 use crate::worker::WorkerManager;
+use crate::Config;
 use anyhow::{anyhow, Result};
 use askama::Template;
+use chrono::Utc;
 use http_body_util::BodyExt;
 use http_body_util::Full;
 use hyper::body::{Bytes, Incoming};
 use hyper::{Request, Response};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OwnedMutexGuard;
 
 #[derive(Template)]
 #[template(path = "submission.html")]
@@ -20,9 +29,64 @@ pub struct SubmissionSite {
     points: i32,
     max_points: i32,
     result: String,
+    submitted_by: Option<String>,
     sidebar_context: SidebarContext,
 }
 
+/// limits how often users can submit, admins are not limited
+#[derive(Clone)]
+pub struct SubmissionLimits {
+    cooldown: Duration,
+    max_submissions_per_problem: i32,
+    last_submission: Arc<Mutex<HashMap<UserId, Instant>>>,
+    // one lock per user, held while a submission is checked against the limits and stored
+    submitting: Arc<Mutex<HashMap<UserId, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+impl SubmissionLimits {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            cooldown: Duration::from_secs(config.submission_cooldown),
+            max_submissions_per_problem: config.max_submissions_per_problem,
+            last_submission: Arc::new(Mutex::new(HashMap::new())),
+            submitting: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// waits until no other submission of the user is being checked or stored,
+    /// so that concurrent submissions cannot pass the limits together
+    async fn lock_user(&self, user_id: UserId) -> Result<OwnedMutexGuard<()>> {
+        let lock = self.submitting.lock().map_err(|e| anyhow!("{e}"))?.entry(user_id).or_default().clone();
+        Ok(lock.lock_owned().await)
+    }
+
+    /// returns the reason why the user is not allowed to submit, if there is one.
+    /// the user has to be locked with `lock_user` until the submission is stored and recorded
+    async fn check_limits(&self, database: &Database, user_id: UserId, contest_id: ContestId, problem_id: ProblemId) -> Result<Option<String>> {
+        if self.max_submissions_per_problem > 0 && database.get_submission_count_by_user_for_problem(user_id, contest_id, problem_id).await? >= self.max_submissions_per_problem as i64 {
+            return Ok(Some(format!("You have reached the limit of {} submissions for this problem.", self.max_submissions_per_problem)));
+        }
+
+        if let Some(last) = self.last_submission.lock().map_err(|e| anyhow!("{e}"))?.get(&user_id) {
+            let elapsed = last.elapsed();
+            if elapsed < self.cooldown {
+                return Ok(Some(format!(
+                    "You have to wait {} more seconds before submitting again.",
+                    self.cooldown.saturating_sub(elapsed).as_secs() + 1
+                )));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// starts the cooldown of the user, only after the submission was stored, so that a failed submission does not count
+    fn record_submission(&self, user_id: UserId) -> Result<()> {
+        self.last_submission.lock().map_err(|e| anyhow!("{e}"))?.insert(user_id, Instant::now());
+        Ok(())
+    }
+}
+
 fn split_bytes_by_bytes(data: &[u8], splitter: &[u8]) -> Vec<Vec<u8>> {
     let mut res = Vec::new();
     let mut current = Vec::new();
@@ -99,19 +163,52 @@ pub async fn extract_file_from_request(request: Request<Incoming>) -> Result<Vec
     Ok(join_bytes(&code_parts, b"\r\n"))
 }
 
+/// the submission is added for the team of the member, or for the member themselves if they are not in a team
 pub async fn handle_submission_form(
     database: &Database,
-    user_id: UserId,
+    member_id: UserId,
     contest_id: &str,
     problem_id: &str,
     request: Request<Incoming>,
     workers: &WorkerManager,
+    submission_limits: &SubmissionLimits,
 ) -> Result<Option<Response<Full<Bytes>>>> {
+    let (Ok(parsed_contest_id), Ok(parsed_problem_id)) = (contest_id.parse::<ContestId>(), problem_id.parse::<ProblemId>()) else {
+        return Ok(None);
+    };
+    let user_id = database.get_acting_user(member_id).await?;
+    if !database.is_contest_id_valid(parsed_contest_id).await || !can_access_problem(database, user_id, parsed_contest_id, parsed_problem_id).await? {
+        return Ok(None);
+    }
+    let is_admin = database.is_user_admin(user_id).await?;
+    if !is_admin {
+        let now = Utc::now();
+        let error = match database.get_user_contest_times(user_id, parsed_contest_id).await? {
+            None => Some("You have not started the contest yet"),
+            Some(times) if !times.has_started(now) => Some("The contest has not started yet"),
+            Some(times) if times.has_ended(now) => Some("The contest has ended"),
+            Some(_) => None,
+        };
+        if let Some(error) = error {
+            return create_problem_page(database, contest_id, problem_id, user_id, error.to_owned()).await;
+        }
+    }
+
     let code = extract_file_from_request(request).await?;
     let code = String::from_utf8_lossy(&code).to_string();
 
     if !code.is_empty() {
-        database.add_submission(user_id, problem_id.parse()?, code, workers).await?;
+        let _user_guard = submission_limits.lock_user(user_id).await?;
+        if !is_admin {
+            if let Some(error) = submission_limits.check_limits(database, user_id, parsed_contest_id, parsed_problem_id).await? {
+                return create_problem_page(database, contest_id, problem_id, user_id, error).await;
+            }
+        }
+
+        database.add_submission(user_id, member_id, parsed_contest_id, parsed_problem_id, code, workers).await?;
+        if !is_admin {
+            submission_limits.record_submission(user_id)?;
+        }
     }
 
     Ok(Some(create_html_response(&RedirectSite {
@@ -119,59 +216,57 @@ pub async fn handle_submission_form(
     })?))
 }
 
-pub async fn create_submission_page(database: &Database, submission_id: &str, user: UserId) -> Result<Option<Response<Full<Bytes>>>> {
-    if let Ok(submission_id) = submission_id.parse() {
-        let code = database.get_submission_code(submission_id).await?;
-        let subtasks = database.get_subtasks_for_submission(submission_id).await?;
-        let mut subtask_vec = Vec::new();
-        for subtask in subtasks {
-            let tests = database.get_tests_for_subtask(subtask).await?;
-            let mut test_vec = Vec::new();
-
-            for test in tests {
-                let time = database.get_test_time(submission_id, test).await?.unwrap_or(0);
-                let test_result = database.get_test_result(submission_id, test).await?;
-
-                let color = match test_result {
-                    TestingResult::InQueue | TestingResult::Compiling | TestingResult::Testing => "#909090",
-                    TestingResult::Accepted => "#00FF00",
-                    TestingResult::WrongAnswer
-                    | TestingResult::RuntimeError
-                    | TestingResult::TimeLimitExceeded
-                    | TestingResult::MemoryLimitExceeded
-                    | TestingResult::CompilationError
-                    | TestingResult::InternalError => "#FF0000",
-                }
-                .to_owned();
+/// only admins and the owner of the submission can see it, and only under the contest and problem it was submitted to
+pub async fn create_submission_page(database: &Database, contest_id: &str, problem_id: &str, submission_id: &str, user: UserId) -> Result<Option<Response<Full<Bytes>>>> {
+    let (Ok(contest_id), Ok(problem_id), Ok(submission_id)) = (contest_id.parse::<ContestId>(), problem_id.parse::<ProblemId>(), submission_id.parse()) else {
+        return Ok(None);
+    };
 
-                test_vec.push((testing_result_to_string(test_result), color, time));
-            }
-
-            let points = database.get_subtask_points_result(submission_id, subtask).await?.unwrap_or(0);
-            let max_points = database.get_subtask_total_points(subtask).await?;
+    let Some(submission) = database.get_submission_view(submission_id).await? else {
+        return Ok(None);
+    };
+    if submission.contest_id != Some(contest_id) || submission.problem_id != problem_id {
+        return Ok(None);
+    }
+    if !database.is_user_admin(user).await? && submission.user_id != Some(database.get_acting_user(user).await?) {
+        return Ok(None);
+    }
 
-            let result = database.get_subtask_result(submission_id, subtask).await?;
-            let hide_score = result == TestingResult::InQueue || result == TestingResult::Testing || result == TestingResult::CompilationError || result == TestingResult::Compiling;
+    let mut subtask_vec = Vec::new();
+    for subtask in submission.subtasks {
+        let mut test_vec = Vec::new();
 
-            let message = testing_result_to_short_string(result);
+        for (test_result, time) in subtask.tests {
+            let color = match test_result {
+                TestingResult::InQueue | TestingResult::Compiling | TestingResult::Testing => "#909090",
+                TestingResult::Accepted => "#00FF00",
+                TestingResult::WrongAnswer
+                | TestingResult::RuntimeError
+                | TestingResult::TimeLimitExceeded
+                | TestingResult::MemoryLimitExceeded
+                | TestingResult::CompilationError
+                | TestingResult::InternalError => "#FF0000",
+            }
+            .to_owned();
 
-            subtask_vec.push((points, max_points, hide_score, message, test_vec));
+            test_vec.push((testing_result_to_string(test_result), color, time));
         }
 
-        let result = database.get_submission_result(submission_id).await?;
-        let points = database.get_submission_points(submission_id).await?.unwrap_or(0);
-        let problem = database.get_submission_problem(submission_id).await?;
-        let max_points = database.get_problem_total_points(problem).await?.max(1);
-
-        return Ok(Some(create_html_response(&SubmissionSite {
-            code,
-            subtasks: subtask_vec,
-            points,
-            max_points,
-            result: testing_result_to_string(result),
-            sidebar_context: create_sidebar_context(database, Some(user)).await?,
-        })?));
+        let result = subtask.result;
+        let hide_score = result == TestingResult::InQueue || result == TestingResult::Testing || result == TestingResult::CompilationError || result == TestingResult::Compiling;
+
+        let message = testing_result_to_short_string(result);
+
+        subtask_vec.push((subtask.points, subtask.max_points, hide_score, message, test_vec));
     }
 
-    Ok(None)
+    Ok(Some(create_html_response(&SubmissionSite {
+        code: submission.code,
+        subtasks: subtask_vec,
+        points: submission.points,
+        max_points: submission.max_points,
+        result: testing_result_to_string(submission.result),
+        submitted_by: submission.submitted_by,
+        sidebar_context: create_sidebar_context(database, Some(user)).await?,
+    })?))
 }
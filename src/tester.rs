@@ -28,17 +28,17 @@ pub async fn execute_test(official_input: &str, official_output: &str, executabl
 
 pub async fn execute_test_unsafely(official_input: &str, official_output: &str, executable_path: &Path, time_limit: i32) -> Result<(TestingResult, i32)> {
     let start_time = tokio::time::Instant::now();
-    let mut child = Command::new(executable_path.as_os_str()).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+    let mut child = Command::new(executable_path.as_os_str()).stdin(Stdio::piped()).stdout(Stdio::piped()).kill_on_drop(true).spawn()?;
 
     let stdin = child.stdin.as_mut().ok_or_else(|| anyhow::anyhow!("Failed to open stdin"))?;
     stdin.write_all(official_input.as_bytes()).await?;
 
-    let process = tokio::spawn(tokio::time::timeout(Duration::from_millis(time_limit as u64), async move {
+    // the child is awaited in place, so it gets killed if the worker running this test is stopped
+    let process = tokio::time::timeout(Duration::from_millis(time_limit as u64), async move {
         let status = child.wait_with_output().await?;
         anyhow::Ok(status)
-    }));
-
-    let process = process.await?;
+    })
+    .await;
 
     if let Ok(output) = process {
         let output = String::from_utf8(output?.stdout)?;
@@ -103,6 +103,7 @@ pub async fn execute_test_isolated(official_input: &str, official_output: &str,
         .arg(executable_name)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
+        .kill_on_drop(true)
         .spawn()?;
 
     let stdin = child.stdin.as_mut().ok_or_else(|| anyhow::anyhow!("Failed to open stdin"))?;
@@ -3,11 +3,23 @@
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod bombardment_tests {
+    use crate::database::contest::ContestId;
+    use crate::database::problem::ProblemId;
+    use crate::database::submission::TestingResult;
     use crate::database::Database;
+    use crate::worker::WorkerManager;
     use crate::{create_database, get_config, Config};
 
     const CONFIG_FILE: &str = "testing_config.toml";
 
+    // size of the contest used to check that pages do not query the database for every row
+    const BENCHMARK_USERS: usize = 300;
+    const BENCHMARK_PROBLEMS: usize = 3;
+    const BENCHMARK_SUBTASKS: usize = 5;
+    const BENCHMARK_TESTS_PER_SUBTASK: usize = 10;
+    // no page should need more queries than this, no matter how big the contest is
+    const MAX_QUERIES_PER_PAGE: u64 = 20;
+
     async fn start_server(config: Config, database: Database) -> (tokio::task::JoinHandle<()>, String) {
         let test_user = database.add_user_override("test", "test", true).await.unwrap();
         let token = database.add_token(test_user).await.unwrap();
@@ -78,4 +90,109 @@ mod bombardment_tests {
 
         server.abort();
     }
+
+    async fn create_benchmark_contest(database: &Database, test_user: i32) -> (ContestId, Vec<ProblemId>) {
+        let contest = match database.get_contest_from_name("query benchmark").await.unwrap() {
+            Some(contest) => contest,
+            None => database.add_contest("query benchmark").await.unwrap(),
+        };
+
+        // hashing passwords is slow, so users are created in parallel batches
+        let usernames: Vec<String> = (0..BENCHMARK_USERS).map(|i| format!("benchmark_user_{i}")).collect();
+        for batch in usernames.chunks(16) {
+            let mut tasks = Vec::new();
+            for username in batch {
+                let database = database.clone();
+                let username = username.clone();
+                tasks.push(tokio::spawn(async move {
+                    if database.get_user_from_username(&username).await.unwrap().is_none() {
+                        let user = database.add_user(&username, "test", false).await.unwrap();
+                        database.add_user_to_contest(user, contest).await.unwrap();
+                    }
+                }));
+            }
+            for task in tasks {
+                task.await.unwrap();
+            }
+        }
+
+        let mut problems = Vec::new();
+        for i in 0..BENCHMARK_PROBLEMS {
+            let problem_name = format!("query benchmark {i}");
+            if let Ok(problem) = database.get_problem_id_from_name(&problem_name).await {
+                problems.push(problem);
+                continue;
+            }
+
+            let problem = database.add_problem(&problem_name, "", 1000).await.unwrap();
+            for _ in 0..BENCHMARK_SUBTASKS {
+                let subtask = database.add_subtask(problem, 10).await.unwrap();
+                for _ in 0..BENCHMARK_TESTS_PER_SUBTASK {
+                    let test = database.add_test("1 2", "3", problem).await.unwrap();
+                    database.add_test_to_subtask(subtask, test).await.unwrap();
+                }
+            }
+            database.add_problem_to_contest(contest, problem).await.unwrap();
+            problems.push(problem);
+        }
+
+        if !database.is_user_in_contest(test_user, contest).await.unwrap() {
+            database.add_user_to_contest(test_user, contest).await.unwrap();
+        }
+
+        (contest, problems)
+    }
+
+    async fn count_page_queries(database: &Database, cookies: &str, port: u16, url: &str) -> u64 {
+        let client = reqwest::Client::new();
+        let before = database.get_query_count();
+        let response = client.get(format!("http://127.0.0.1:{port}/{url}")).header(reqwest::header::COOKIE, cookies).send().await.unwrap();
+        assert!(response.status().is_success());
+        database.get_query_count() - before
+    }
+
+    // fails if a page starts doing a query for every user, problem or test again
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore = "needs a database and takes a while"]
+    async fn bombardment_page_queries() {
+        let config = get_config(CONFIG_FILE).unwrap();
+        let port = config.port;
+        let database = create_database(&config).await.unwrap();
+
+        let (server, cookies) = start_server(config.clone(), database.clone()).await;
+        let test_user = database.get_user_from_username("test").await.unwrap().unwrap();
+
+        let (contest, problems) = create_benchmark_contest(&database, test_user).await;
+
+        let workers = WorkerManager::new(&config, &database);
+        let problem = *problems.first().unwrap();
+        let submission = database.add_submission(test_user, test_user, contest, problem, "int main() {}".to_owned(), &workers).await.unwrap();
+        drop(workers);
+
+        // judging does a lot of queries, so wait for it to finish before counting
+        while matches!(
+            database.get_submission_result(submission).await.unwrap(),
+            TestingResult::InQueue | TestingResult::Compiling | TestingResult::Testing
+        ) {
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        }
+
+        let mut urls = vec![String::new(), format!("contest/{contest}"), format!("contest/{contest}/problem/{problem}/submission/{submission}")];
+        for problem in &problems {
+            urls.push(format!("contest/{contest}/problem/{problem}"));
+        }
+
+        for url in urls {
+            let queries = count_page_queries(&database, &cookies, port, &url).await;
+            println!("/{url}: {queries} queries");
+            assert!(queries <= MAX_QUERIES_PER_PAGE, "/{url} needed {queries} queries");
+
+            let start = std::time::Instant::now();
+            bombard_url(cookies.clone(), port, &url).await;
+            println!("/{url}: 1000 requests in {}ms", start.elapsed().as_millis());
+        }
+
+        drop(database);
+        server.abort();
+    }
 }
@@ -1 +1,3 @@
 mod bombardment;
+mod postgres;
+mod sqlite;
@@ -0,0 +1,89 @@
+// these tests run on the postgres database from the testing config, everything they write is rolled back
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests should panic on errors")]
+mod postgres_tests {
+    use crate::database::contest::{ContestTimes, ContestType};
+    use crate::database::storage::Backend;
+    use crate::database::submission::{testing_result_to_i32, TestingResult};
+    use crate::database::DatabaseQuery;
+    use crate::{create_database, get_config};
+    use chrono::{DateTime, Duration};
+
+    const CONFIG_FILE: &str = "testing_config.toml";
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database"]
+    async fn migrations_and_scoreboards() {
+        static SUBMIT_QUERY: DatabaseQuery =
+            DatabaseQuery::new("INSERT INTO submissions (user_id, contest_id, problem_id, code, result, tests_done, submitted_at) VALUES ($1, $2, $3, '', $4, 1, $5) RETURNING submission_id");
+        static SUBTASK_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO subtask_results (submission_id, subtask_id, result) VALUES ($1, $2, $3)");
+        static TEST_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO test_results (submission_id, test_id, result) VALUES ($1, $2, $3)");
+
+        let database = create_database(&get_config(CONFIG_FILE).unwrap()).await.unwrap();
+        assert_eq!(database.backend(), Backend::Postgres);
+        assert!(database.get_pending_migrations().await.unwrap().is_empty());
+
+        // never committed, so the database is left as it was
+        let transaction = database.begin_transaction().await.unwrap();
+
+        let a = transaction.add_user("postgres_test_a", "password", false).await.unwrap();
+        let b = transaction.add_user("postgres_test_b", "password", false).await.unwrap();
+        let contest = transaction.add_contest("postgres test").await.unwrap();
+        let problem = transaction.add_problem("problem", "description", 1000).await.unwrap();
+        let subtask = transaction.add_subtask(problem, 40).await.unwrap();
+        let test = transaction.add_test("1", "1", problem).await.unwrap();
+        transaction.add_test_to_subtask(subtask, test).await.unwrap();
+        transaction.add_problem_to_contest(contest, problem).await.unwrap();
+        transaction.add_user_to_contest(a, contest).await.unwrap();
+        transaction.add_user_to_contest(b, contest).await.unwrap();
+
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        for (user, result, minute) in [(a, TestingResult::Accepted, 10), (b, TestingResult::WrongAnswer, 5)] {
+            let submitted_at = start + Duration::try_minutes(minute).unwrap();
+            let rows = SUBMIT_QUERY
+                .execute(&transaction, &[&user, &contest, &problem, &testing_result_to_i32(result), &submitted_at])
+                .await
+                .unwrap();
+            let submission: i32 = rows.first().unwrap().get(0);
+            SUBTASK_QUERY
+                .execute(&transaction, &[&submission, &subtask, &testing_result_to_i32(TestingResult::Testing)])
+                .await
+                .unwrap();
+            TEST_QUERY.execute(&transaction, &[&submission, &test, &testing_result_to_i32(result)]).await.unwrap();
+            transaction.update_submission_result(submission).await.unwrap();
+        }
+
+        let scoreboard = transaction.get_scoreboard(contest, None).await.unwrap();
+        let rows = scoreboard.rows.iter().map(|row| (row.rank, row.username.as_str(), row.total)).collect::<Vec<_>>();
+        assert_eq!(rows, [(1, "postgres_test_a", 40), (2, "postgres_test_b", 0)]);
+
+        transaction.set_contest_type(contest, ContestType::Icpc).await.unwrap();
+        let times = ContestTimes {
+            start_time: Some(start),
+            end_time: None,
+            freeze_time: None,
+            duration_minutes: None,
+        };
+        transaction.set_contest_times(contest, times).await.unwrap();
+        let scoreboard = transaction.get_scoreboard(contest, Some(b)).await.unwrap();
+        let rows = scoreboard.rows.iter().map(|row| (row.rank, row.username.as_str(), row.total, row.penalty)).collect::<Vec<_>>();
+        assert_eq!(rows, [(1, "postgres_test_a", 1, 10), (2, "postgres_test_b", 0, 0)]);
+        assert_eq!(transaction.get_contest_overviews_for_user(a).await.unwrap().len(), 1);
+        assert!(transaction.check_integrity().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database"]
+    async fn parameter_types() {
+        static BIGINT_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT $1::BIGINT + 1");
+        static INTEGER_QUERY: DatabaseQuery = DatabaseQuery::new("SELECT user_id FROM users WHERE user_id = $1");
+
+        let database = create_database(&get_config(CONFIG_FILE).unwrap()).await.unwrap();
+
+        // integers are sent with the size of the parameter, other values are rejected
+        let rows = BIGINT_QUERY.execute(&database, &[&41]).await.unwrap();
+        assert_eq!(rows.first().unwrap().get::<i64>(0), 42);
+        assert!(INTEGER_QUERY.execute(&database, &[&"1"]).await.is_err());
+    }
+}
@@ -0,0 +1,896 @@
+// these tests run on a temporary sqlite database, so they do not need a database server
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests should panic on errors")]
+mod sqlite_tests {
+    use crate::database::audit::AuditFilter;
+    use crate::database::backup::Backup;
+    use crate::database::contest::{ContestTimes, ContestType};
+    use crate::database::problem::{get_letter_label, get_letter_position};
+    use crate::database::scoreboard::Scoreboard;
+    use crate::database::storage::Backend;
+    use crate::database::submission::{testing_result_to_i32, TestingResult};
+    use crate::database::{Database, DatabaseQuery};
+    use crate::problem::{can_access_problem, create_problem_page};
+    use crate::submission::create_submission_page;
+    use crate::user::delete_user;
+    use crate::{create_database, get_config};
+    use chrono::{DateTime, Duration, Utc};
+    use std::net::IpAddr;
+
+    async fn create_temporary_database(name: &str) -> Database {
+        let config_file = std::env::temp_dir().join(format!("cps_{name}_{}.toml", std::process::id()));
+        std::fs::write(&config_file, "db_backend = \"sqlite\"\ndb_file = \":memory:\"\n").unwrap();
+        let config = get_config(config_file.to_str().unwrap()).unwrap();
+        std::fs::remove_file(config_file).unwrap();
+
+        create_database(&config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn migrations_are_applied() {
+        let database = create_temporary_database("migrations").await;
+
+        assert_eq!(database.backend(), Backend::Sqlite);
+        assert!(database.get_schema_version().await.unwrap() > 0);
+        assert!(database.get_pending_migrations().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn users_and_tokens() {
+        let database = create_temporary_database("users").await;
+
+        let user = database.add_user("user", "password", false).await.unwrap();
+        assert_eq!(database.get_user_from_username("user").await.unwrap(), Some(user));
+        assert_eq!(database.get_username(user).await.unwrap(), Some("user".to_owned()));
+        assert!(!database.is_user_admin(user).await.unwrap());
+
+        let token = database.add_token(user).await.unwrap();
+        assert_eq!(database.get_user_from_token(token).await.unwrap(), Some(user));
+    }
+
+    #[tokio::test]
+    async fn contest_overviews() {
+        let database = create_temporary_database("contests").await;
+
+        let admin = database.add_user("admin", "password", true).await.unwrap();
+        let user = database.add_user("user", "password", false).await.unwrap();
+        let contest = database.add_contest("contest").await.unwrap();
+        let empty_contest = database.add_contest("empty contest").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        database.add_subtask(problem, 40).await.unwrap();
+        database.add_problem_to_contest(contest, problem).await.unwrap();
+        database.add_user_to_contest(user, contest).await.unwrap();
+
+        let overviews = database.get_contest_overviews_for_user(user).await.unwrap();
+        assert_eq!(overviews.len(), 1);
+        assert_eq!(overviews.first().unwrap().2, vec![(problem, "A".to_owned(), "problem".to_owned(), 0, 40)]);
+
+        // admins see every contest
+        let overviews = database.get_contest_overviews_for_user(admin).await.unwrap();
+        assert_eq!(overviews.iter().map(|contest| contest.0).collect::<Vec<_>>(), vec![contest, empty_contest]);
+
+        let participations = database.get_all_users_with_participation(contest).await.unwrap();
+        assert_eq!(participations, vec![(admin, "admin".to_owned(), false), (user, "user".to_owned(), true)]);
+    }
+
+    #[tokio::test]
+    async fn per_contest_scores() {
+        static SUBMIT_QUERY: DatabaseQuery =
+            DatabaseQuery::new("INSERT INTO submissions (user_id, contest_id, problem_id, code, result, tests_done, submitted_at) VALUES ($1, $2, $3, '', $4, 0, $5) RETURNING submission_id");
+        static POINTS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO subtask_results (submission_id, subtask_id, result, points) VALUES ($1, $2, $3, $4)");
+
+        let database = create_temporary_database("reuse").await;
+
+        let user = database.add_user("user", "password", false).await.unwrap();
+        let old_contest = database.add_contest("old contest").await.unwrap();
+        let new_contest = database.add_contest("new contest").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        let subtask = database.add_subtask(problem, 40).await.unwrap();
+        for contest in [old_contest, new_contest] {
+            database.add_problem_to_contest(contest, problem).await.unwrap();
+            database.add_user_to_contest(user, contest).await.unwrap();
+        }
+
+        let accepted = testing_result_to_i32(TestingResult::Accepted);
+        let time = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let rows = SUBMIT_QUERY.execute(&database, &[&user, &old_contest, &problem, &accepted, &time]).await.unwrap();
+        let submission: i32 = rows.first().unwrap().get(0);
+        POINTS_QUERY.execute(&database, &[&submission, &subtask, &accepted, &40]).await.unwrap();
+        database.update_submission_result(submission).await.unwrap();
+
+        // the solution from the old contest does not count in the new one
+        assert_eq!(database.get_user_score_for_problem(user, old_contest, problem).await.unwrap(), 40);
+        assert_eq!(database.get_user_score_for_problem(user, new_contest, problem).await.unwrap(), 0);
+        assert!(database.get_submission_summaries_for_user_problem(user, new_contest, problem).await.unwrap().is_empty());
+        let scores = database
+            .get_contest_overviews_for_user(user)
+            .await
+            .unwrap()
+            .iter()
+            .map(|contest| contest.2.first().unwrap().3)
+            .collect::<Vec<_>>();
+        assert_eq!(scores, [40, 0]);
+        let scoreboard = database.get_scoreboard(new_contest, None).await.unwrap();
+        assert_eq!(scoreboard.rows.first().unwrap().total, 0);
+        assert!(database.check_integrity().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn problem_order() {
+        let database = create_temporary_database("order").await;
+
+        let contest = database.add_contest("contest").await.unwrap();
+        let mut problems = Vec::new();
+        for name in ["first", "second", "third"] {
+            let problem = database.add_problem(name, "description", 1000).await.unwrap();
+            database.add_problem_to_contest(contest, problem).await.unwrap();
+            problems.push(problem);
+        }
+        let [first, second, third]: [i32; 3] = problems.try_into().unwrap();
+        let labels = database.get_problem_names_for_contest(contest).await.unwrap();
+        assert_eq!(labels.iter().map(|(_, label, _)| label.as_str()).collect::<Vec<_>>(), ["A", "B", "C"]);
+
+        database
+            .set_problem_order(contest, &[(third, "1".to_owned()), (first, "2".to_owned()), (second, "3".to_owned())])
+            .await
+            .unwrap();
+        assert_eq!(database.get_problems_for_contest(contest).await.unwrap(), [third, first, second]);
+        assert_eq!(database.get_problem_label(contest, first).await.unwrap(), Some("2".to_owned()));
+
+        // new problems continue the numbers
+        let fourth = database.add_problem("fourth", "description", 1000).await.unwrap();
+        database.add_problem_to_contest(contest, fourth).await.unwrap();
+        assert_eq!(database.get_problems_for_contest(contest).await.unwrap().last(), Some(&fourth));
+        assert_eq!(database.get_problem_label(contest, fourth).await.unwrap(), Some("4".to_owned()));
+
+        assert_eq!([0, 25, 26, 27, 701, 702].map(get_letter_label), ["A", "Z", "AA", "AB", "ZZ", "AAA"]);
+        for position in [0, 25, 26, 27, 701, 702] {
+            assert_eq!(get_letter_position(&get_letter_label(position)), Some(position));
+        }
+        assert_eq!(get_letter_position("1"), None);
+
+        // letters continue from the last label, even after a problem in between was removed
+        let letters = database.add_contest("letters").await.unwrap();
+        for problem in [first, second, third] {
+            database.add_problem_to_contest(letters, problem).await.unwrap();
+        }
+        database.remove_problem_from_contest(letters, second).await.unwrap();
+        database.add_problem_to_contest(letters, fourth).await.unwrap();
+        assert_eq!(database.get_problem_label(letters, fourth).await.unwrap(), Some("D".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn transactions() {
+        let database = create_temporary_database("transactions").await;
+
+        let transaction = database.begin_transaction().await.unwrap();
+        transaction.add_contest("rolled back").await.unwrap();
+        drop(transaction);
+        assert_eq!(database.get_contest_from_name("rolled back").await.unwrap(), None);
+
+        let transaction = database.begin_transaction().await.unwrap();
+        let contest = transaction.add_contest("committed").await.unwrap();
+        transaction.commit().await.unwrap();
+        assert_eq!(database.get_contest_from_name("committed").await.unwrap(), Some(contest));
+
+        let user = database.add_user("user", "password", false).await.unwrap();
+        database.add_user_to_contest(user, contest).await.unwrap();
+        database.add_token(user).await.unwrap();
+        database.delete_user(user).await.unwrap();
+        assert_eq!(database.get_user_from_username("user").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn cascading_deletes() {
+        let database = create_temporary_database("deletes").await;
+
+        let user = database.add_user("user", "password", false).await.unwrap();
+        let contest = database.add_contest("contest").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        let subtask = database.add_subtask(problem, 40).await.unwrap();
+        let test = database.add_test("1", "1", problem).await.unwrap();
+        database.add_test_to_subtask(subtask, test).await.unwrap();
+        database.add_problem_to_contest(contest, problem).await.unwrap();
+        database.add_user_to_contest(user, contest).await.unwrap();
+        database.update_user_score_for_problem(user, contest, problem).await.unwrap();
+
+        database.remove_problem(problem).await.unwrap();
+        assert!(!database.is_problem_id_valid(problem).await);
+        assert!(database.get_problems_for_contest(contest).await.unwrap().is_empty());
+        assert!(database.get_subtasks_for_problem(problem).await.unwrap().is_empty());
+        assert!(database.get_tests_for_subtask(subtask).await.unwrap().is_empty());
+
+        database.remove_contest(contest).await.unwrap();
+        assert!(!database.is_contest_id_valid(contest).await);
+        assert!(database.get_contests_for_user(user).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn backup_and_restore() {
+        let database = create_temporary_database("backup").await;
+
+        let user = database.add_user("user", "password", false).await.unwrap();
+        let contest = database.add_contest("contest").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        let subtask = database.add_subtask(problem, 40).await.unwrap();
+        database.add_test_to_subtask(subtask, database.add_test("1 2", "3", problem).await.unwrap()).await.unwrap();
+        database.add_problem_to_contest(contest, problem).await.unwrap();
+        database.add_user_to_contest(user, contest).await.unwrap();
+        database.update_user_score_for_problem(user, contest, problem).await.unwrap();
+        let times = ContestTimes {
+            start_time: Some(Utc::now()),
+            end_time: None,
+            freeze_time: None,
+            duration_minutes: None,
+        };
+        database.set_contest_times(contest, times).await.unwrap();
+        database.start_participation(user, contest, Utc::now()).await.unwrap();
+        database.add_clarification(user, contest, Some(problem), "question").await.unwrap();
+        let announcement = database.add_announcement(contest, None, "announcement").await.unwrap();
+        database.mark_announcements_read(user, &[announcement]).await.unwrap();
+        // the team is created after its member, so it has the larger id
+        let member = database.add_user("member", "password", false).await.unwrap();
+        let team = database.add_team("team", "password").await.unwrap();
+        database.set_user_team(member, Some(team)).await.unwrap();
+
+        let backup = database.create_backup().await.unwrap();
+        let mut archive = Vec::new();
+        backup.write(&mut archive).unwrap();
+
+        let restored = create_temporary_database("restored").await;
+        restored.restore_backup(&Backup::read(archive.as_slice()).unwrap()).await.unwrap();
+        assert_eq!(restored.create_backup().await.unwrap(), backup);
+        assert!(restored.try_login("user", "password").await.unwrap().is_some());
+
+        // a backup is never merged into existing data
+        restored.restore_backup(&backup).await.unwrap_err();
+        Backup::read(&b"not a backup"[..]).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn audit_log() {
+        let database = create_temporary_database("audit").await;
+
+        let admin = database.add_user("admin", "password", true).await.unwrap();
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        let actor = database.get_audit_actor(admin, ip).await.unwrap();
+        database.add_audit_entry(&actor, "create_user", "user 2", "", "user, admin: false").await.unwrap();
+        database.add_audit_entry(&actor, "delete_user", "user 2", "user, admin: false", "").await.unwrap();
+
+        let entries = database.get_audit_entries(&AuditFilter::default()).await.unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.action.as_str()).collect::<Vec<_>>(), vec!["delete_user", "create_user"]);
+        assert_eq!(entries.first().unwrap().ip_address, "127.0.0.1");
+
+        let filter = AuditFilter {
+            user_id: Some(admin),
+            action: "create_user".to_owned(),
+        };
+        assert_eq!(database.get_audit_entries(&filter).await.unwrap().len(), 1);
+
+        // an admin deleting themselves is still logged and the entries keep the username
+        delete_user(&database, admin, admin, ip).await.unwrap();
+        assert!(database.get_username(admin).await.unwrap().is_none());
+        let entries = database.get_audit_entries(&AuditFilter::default()).await.unwrap();
+        assert_eq!(entries.len(), 3);
+        let entry = entries.first().unwrap();
+        assert_eq!(
+            (entry.username.as_str(), entry.action.as_str(), entry.target.as_str()),
+            ("admin", "delete_user", format!("user {admin}").as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn cache_invalidation() {
+        let database = create_temporary_database("cache").await;
+
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        assert_eq!(database.get_problem_name(problem).await.unwrap(), "problem");
+        assert_eq!(database.get_problem_name(problem).await.unwrap(), "problem");
+        let stats = database.get_database_status().unwrap().caches.into_iter().find(|cache| cache.name == "problem names").unwrap();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+
+        database.set_problem_name(problem, "renamed").await.unwrap();
+        assert_eq!(database.get_problem_name(problem).await.unwrap(), "renamed");
+
+        database.add_subtask(problem, 40).await.unwrap();
+        assert_eq!(database.get_problem_total_points(problem).await.unwrap(), 40);
+        database.add_subtask(problem, 60).await.unwrap();
+        assert_eq!(database.get_problem_total_points(problem).await.unwrap(), 100);
+
+        // changes inside of a transaction are only visible once it is committed
+        let transaction = database.begin_transaction().await.unwrap();
+        transaction.set_problem_name(problem, "rolled back").await.unwrap();
+        assert_eq!(transaction.get_problem_name(problem).await.unwrap(), "rolled back");
+        drop(transaction);
+        assert_eq!(database.get_problem_name(problem).await.unwrap(), "renamed");
+
+        // committing only forgets the values that the transaction changed
+        let other = database.add_problem("other", "description", 1000).await.unwrap();
+        assert_eq!(database.get_problem_name(other).await.unwrap(), "other");
+        let transaction = database.begin_transaction().await.unwrap();
+        transaction.set_problem_name(problem, "committed").await.unwrap();
+        transaction.commit().await.unwrap();
+        let before = database.get_database_status().unwrap().caches.into_iter().find(|cache| cache.name == "problem names").unwrap();
+        assert_eq!(database.get_problem_name(other).await.unwrap(), "other");
+        assert_eq!(database.get_problem_name(problem).await.unwrap(), "committed");
+        let after = database.get_database_status().unwrap().caches.into_iter().find(|cache| cache.name == "problem names").unwrap();
+        assert_eq!((after.hits - before.hits, after.misses - before.misses), (1, 1));
+
+        let transaction = database.begin_transaction().await.unwrap();
+        transaction.remove_problem(problem).await.unwrap();
+        transaction.commit().await.unwrap();
+        assert!(!database.is_problem_id_valid(problem).await);
+
+        let user = database.add_user("user", "password", true).await.unwrap();
+        assert!(database.is_user_admin(user).await.unwrap());
+        database.delete_user(user).await.unwrap();
+        database.is_user_admin(user).await.unwrap_err();
+        assert_eq!(database.get_username(user).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn integrity_repair() {
+        static CORRUPT_SCORES_QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE user_problem_scores SET score = 5");
+        static CORRUPT_POINTS_QUERY: DatabaseQuery = DatabaseQuery::new("UPDATE problems SET points = 7");
+
+        let database = create_temporary_database("integrity").await;
+
+        let user = database.add_user("user", "password", false).await.unwrap();
+        let contest = database.add_contest("contest").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        database.add_subtask(problem, 40).await.unwrap();
+        database.update_user_score_for_problem(user, contest, problem).await.unwrap();
+        assert!(database.check_integrity().await.unwrap().is_empty());
+
+        // removing the test data also resets the points of the problem
+        database.remove_all_test_data_for_problem(problem).await.unwrap();
+        assert_eq!(database.get_problem_total_points(problem).await.unwrap(), 0);
+
+        database.add_subtask(problem, 60).await.unwrap();
+        CORRUPT_SCORES_QUERY.execute(&database, &[]).await.unwrap();
+        CORRUPT_POINTS_QUERY.execute(&database, &[]).await.unwrap();
+        let issues = database.check_integrity().await.unwrap();
+        assert_eq!(
+            issues.iter().map(|issue| (issue.kind, issue.stored, issue.expected)).collect::<Vec<_>>(),
+            [("problem points", 7, 60), ("user score", 5, 0)]
+        );
+
+        assert_eq!(database.repair_integrity().await.unwrap().len(), 2);
+        assert!(database.check_integrity().await.unwrap().is_empty());
+        assert_eq!(database.get_problem_total_points(problem).await.unwrap(), 60);
+        assert_eq!(database.get_user_score_for_problem(user, contest, problem).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn tests_done_count() {
+        static SUBMIT_QUERY: DatabaseQuery =
+            DatabaseQuery::new("INSERT INTO submissions (user_id, contest_id, problem_id, code, result, tests_done, submitted_at) VALUES ($1, $2, $3, '', $4, 0, $5) RETURNING submission_id");
+        static TEST_RESULT_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO test_results (submission_id, test_id, result) VALUES ($1, $2, $3)");
+
+        let database = create_temporary_database("tests_done").await;
+
+        let user = database.add_user("user", "password", false).await.unwrap();
+        let contest = database.add_contest("contest").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        let first = database.add_test("1", "1", problem).await.unwrap();
+        let second = database.add_test("2", "2", problem).await.unwrap();
+        let submission: i32 = SUBMIT_QUERY
+            .execute(&database, &[&user, &contest, &problem, &testing_result_to_i32(TestingResult::Testing), &Utc::now()])
+            .await
+            .unwrap()
+            .first()
+            .unwrap()
+            .get(0);
+        for test in [first, second] {
+            TEST_RESULT_QUERY
+                .execute(&database, &[&submission, &test, &testing_result_to_i32(TestingResult::InQueue)])
+                .await
+                .unwrap();
+        }
+
+        database.set_test_result(submission, first, TestingResult::Accepted).await.unwrap();
+        assert_eq!(database.update_submission_tests_done(submission).await.unwrap(), 1);
+        // counting again does not count the same test twice
+        assert_eq!(database.update_submission_tests_done(submission).await.unwrap(), 1);
+
+        // a test that is retried after its worker died is not counted until it has a result again
+        database.reset_test_result(submission, first).await.unwrap();
+        assert_eq!(database.update_submission_tests_done(submission).await.unwrap(), 0);
+        for test in [first, second] {
+            database.set_test_result(submission, test, TestingResult::Accepted).await.unwrap();
+        }
+        assert_eq!(database.update_submission_tests_done(submission).await.unwrap(), 2);
+        assert_eq!(database.get_submission_tests_done(submission).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn contest_lifecycle() {
+        let database = create_temporary_database("lifecycle").await;
+
+        let contest = database.add_contest("Contest 1!").await.unwrap();
+        assert_eq!(database.get_contest_name(contest).await.unwrap(), "Contest 1!");
+
+        database.set_contest_name(contest, "Renamed").await.unwrap();
+        assert_eq!(database.get_contest_name(contest).await.unwrap(), "Renamed");
+        assert_eq!(database.get_contest_from_name("Renamed").await.unwrap(), Some(contest));
+
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let hour = Duration::try_hours(1).unwrap();
+        let times = ContestTimes {
+            start_time: Some(now - hour),
+            end_time: Some(now + hour),
+            freeze_time: Some(now),
+            duration_minutes: None,
+        };
+        database.set_contest_times(contest, times).await.unwrap();
+        assert_eq!(database.get_contest_times(contest).await.unwrap(), times);
+        assert!(times.is_running(now));
+        assert!(!times.has_started(now - hour - hour));
+        assert!(times.has_ended(now + hour));
+        assert!(times.is_frozen(now) && !times.is_frozen(now - hour));
+        assert!(ContestTimes::default().is_running(now));
+
+        database.remove_contest(contest).await.unwrap();
+        assert!(!database.is_contest_id_valid(contest).await);
+    }
+
+    #[tokio::test]
+    async fn scoreboard_ranks() {
+        static SET_SCORE_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO user_problem_scores (user_id, contest_id, problem_id, score, improved_at) VALUES ($1, $2, $3, $4, $5)");
+
+        let database = create_temporary_database("scoreboard").await;
+
+        let contest = database.add_contest("contest").await.unwrap();
+        let first = database.add_problem("first", "description", 1000).await.unwrap();
+        let second = database.add_problem("second", "description", 1000).await.unwrap();
+        database.add_problem_to_contest(contest, first).await.unwrap();
+        database.add_problem_to_contest(contest, second).await.unwrap();
+
+        let mut users = Vec::new();
+        for name in ["d", "c", "b", "a"] {
+            let user = database.add_user(name, "password", false).await.unwrap();
+            database.add_user_to_contest(user, contest).await.unwrap();
+            users.push(user);
+        }
+        let [d, c, b, _]: [i32; 4] = users.try_into().unwrap();
+        let time = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        for (user, problem, score) in [(d, first, 50), (c, first, 20), (c, second, 30), (b, second, 20)] {
+            SET_SCORE_QUERY.execute(&database, &[&user, &contest, &problem, &score, &time]).await.unwrap();
+        }
+
+        let scoreboard = database.get_scoreboard(contest, None).await.unwrap();
+        assert_eq!(scoreboard.problems.iter().map(|problem| problem.problem_name.as_str()).collect::<Vec<_>>(), ["first", "second"]);
+        let rows = scoreboard
+            .rows
+            .iter()
+            .map(|row| (row.rank, row.username.as_str(), row.cells.iter().map(|cell| cell.score).collect::<Vec<_>>(), row.total))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, [(1, "c", vec![20, 30], 50), (1, "d", vec![50, 0], 50), (3, "b", vec![0, 20], 20), (4, "a", vec![0, 0], 0)]);
+        assert_eq!(scoreboard.rows.first().unwrap().last_improvement, Some(time));
+        assert_eq!(scoreboard.rows.last().unwrap().last_improvement, None);
+    }
+
+    #[tokio::test]
+    async fn score_improvement_time() {
+        static SUBMIT_QUERY: DatabaseQuery =
+            DatabaseQuery::new("INSERT INTO submissions (user_id, contest_id, problem_id, code, result, tests_done, submitted_at) VALUES ($1, $2, $3, '', $4, 0, $5) RETURNING submission_id");
+        static POINTS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO subtask_results (submission_id, subtask_id, result, points) VALUES ($1, $2, $3, $4)");
+
+        let database = create_temporary_database("improvement").await;
+
+        let user = database.add_user("user", "password", false).await.unwrap();
+        let contest = database.add_contest("contest").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        let first = database.add_subtask(problem, 40).await.unwrap();
+        let second = database.add_subtask(problem, 60).await.unwrap();
+        database.add_problem_to_contest(contest, problem).await.unwrap();
+        database.add_user_to_contest(user, contest).await.unwrap();
+
+        // the second submission raises the score, the others only repeat points that were already reached
+        let accepted = testing_result_to_i32(TestingResult::Accepted);
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let minutes = |minutes| start + Duration::try_minutes(minutes).unwrap();
+        for (minute, points) in [(1, [40, 0]), (2, [40, 60]), (3, [0, 60]), (4, [40, 60])] {
+            let time = minutes(minute);
+            let rows = SUBMIT_QUERY.execute(&database, &[&user, &contest, &problem, &accepted, &time]).await.unwrap();
+            let submission: i32 = rows.first().unwrap().get(0);
+            for (subtask, points) in [first, second].into_iter().zip(points) {
+                POINTS_QUERY.execute(&database, &[&submission, &subtask, &accepted, &points]).await.unwrap();
+            }
+            database.update_user_score_for_problem(user, contest, problem).await.unwrap();
+        }
+
+        assert_eq!(database.get_scoreboard(contest, None).await.unwrap().rows.first().unwrap().last_improvement, Some(minutes(2)));
+
+        // repairing the scores keeps the time of the submission instead of the time of the repair
+        database.repair_integrity().await.unwrap();
+        assert_eq!(database.get_scoreboard(contest, None).await.unwrap().rows.first().unwrap().last_improvement, Some(minutes(2)));
+    }
+
+    #[tokio::test]
+    async fn icpc_scoreboard() {
+        static SUBMIT_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO submissions (user_id, contest_id, problem_id, code, result, tests_done, submitted_at) VALUES ($1, $2, $3, '', $4, 0, $5)");
+
+        let database = create_temporary_database("icpc").await;
+
+        let contest = database.add_contest("contest").await.unwrap();
+        database.set_contest_type(contest, ContestType::Icpc).await.unwrap();
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        database
+            .set_contest_times(
+                contest,
+                ContestTimes {
+                    start_time: Some(start),
+                    end_time: None,
+                    freeze_time: None,
+                    duration_minutes: None,
+                },
+            )
+            .await
+            .unwrap();
+        let first = database.add_problem("first", "description", 1000).await.unwrap();
+        let second = database.add_problem("second", "description", 1000).await.unwrap();
+        database.add_problem_to_contest(contest, first).await.unwrap();
+        database.add_problem_to_contest(contest, second).await.unwrap();
+        let a = database.add_user("a", "password", false).await.unwrap();
+        let b = database.add_user("b", "password", false).await.unwrap();
+        database.add_user_to_contest(a, contest).await.unwrap();
+        database.add_user_to_contest(b, contest).await.unwrap();
+
+        for (user, problem, result, minute) in [
+            (b, second, TestingResult::Accepted, -5),
+            (a, first, TestingResult::WrongAnswer, 5),
+            (a, first, TestingResult::CompilationError, 6),
+            (b, first, TestingResult::Accepted, 8),
+            (a, first, TestingResult::Accepted, 10),
+            (b, second, TestingResult::WrongAnswer, 20),
+            (a, second, TestingResult::Accepted, 30),
+            (a, second, TestingResult::WrongAnswer, 40),
+        ] {
+            let submitted_at = start + Duration::try_minutes(minute).unwrap();
+            SUBMIT_QUERY
+                .execute(&database, &[&user, &contest, &problem, &testing_result_to_i32(result), &submitted_at])
+                .await
+                .unwrap();
+        }
+
+        let scoreboard = database.get_scoreboard(contest, None).await.unwrap();
+        let rows = scoreboard
+            .rows
+            .iter()
+            .map(|row| {
+                let cells = row.cells.iter().map(|cell| (cell.solve_minute, cell.rejected_attempts, cell.is_first_solve)).collect::<Vec<_>>();
+                (row.rank, row.username.as_str(), row.total, row.penalty, cells)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            [
+                (1, "a", 2, 60, vec![(Some(10), 1, false), (Some(30), 0, true)]),
+                (2, "b", 1, 8, vec![(Some(8), 0, true), (None, 1, false)])
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn frozen_scoreboard() {
+        static SUBMIT_QUERY: DatabaseQuery =
+            DatabaseQuery::new("INSERT INTO submissions (user_id, contest_id, problem_id, code, result, tests_done, submitted_at) VALUES ($1, $2, $3, '', $4, 0, $5) RETURNING submission_id");
+
+        let database = create_temporary_database("freeze").await;
+
+        let contest = database.add_contest("contest").await.unwrap();
+        database.set_contest_type(contest, ContestType::Icpc).await.unwrap();
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let minutes = |minutes| start + Duration::try_minutes(minutes).unwrap();
+        let times = ContestTimes {
+            start_time: Some(start),
+            end_time: Some(minutes(300)),
+            freeze_time: Some(minutes(240)),
+            duration_minutes: None,
+        };
+        database.set_contest_times(contest, times).await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        database.add_problem_to_contest(contest, problem).await.unwrap();
+        let a = database.add_user("a", "password", false).await.unwrap();
+        let b = database.add_user("b", "password", false).await.unwrap();
+        database.add_user_to_contest(a, contest).await.unwrap();
+        database.add_user_to_contest(b, contest).await.unwrap();
+
+        let mut submissions = Vec::new();
+        for (user, result, minute) in [(a, TestingResult::WrongAnswer, 100), (b, TestingResult::Accepted, 250), (a, TestingResult::WrongAnswer, 260)] {
+            let rows = SUBMIT_QUERY
+                .execute(&database, &[&user, &contest, &problem, &testing_result_to_i32(result), &minutes(minute)])
+                .await
+                .unwrap();
+            submissions.push(rows.first().unwrap().get::<i32>(0));
+        }
+
+        let cells = |scoreboard: &Scoreboard| {
+            scoreboard
+                .rows
+                .iter()
+                .map(|row| {
+                    let cell = row.cells.first().unwrap();
+                    (row.username.clone(), cell.solve_minute, cell.rejected_attempts, cell.pending)
+                })
+                .collect::<Vec<_>>()
+        };
+        let full = database.get_scoreboard(contest, None).await.unwrap();
+        assert_eq!(cells(&full), [("b".to_owned(), Some(250), 0, 0), ("a".to_owned(), None, 2, 0)]);
+        assert_eq!(full.frozen_since, None);
+
+        // a sees their own new attempt, but not the solve of b
+        let seen_by_a = database.get_scoreboard(contest, Some(a)).await.unwrap();
+        assert_eq!(cells(&seen_by_a), [("a".to_owned(), None, 2, 0), ("b".to_owned(), None, 0, 1)]);
+        assert_eq!(seen_by_a.frozen_since, times.freeze_time);
+
+        let frozen = database.get_frozen_submissions(contest).await.unwrap();
+        assert_eq!(frozen.iter().map(|submission| submission.submission_id).collect::<Vec<_>>(), submissions.get(1..).unwrap());
+
+        database.set_revealed_until(contest, submissions.get(1).copied()).await.unwrap();
+        let seen_by_a = database.get_scoreboard(contest, Some(a)).await.unwrap();
+        assert_eq!(cells(&seen_by_a), [("b".to_owned(), Some(250), 0, 0), ("a".to_owned(), None, 2, 0)]);
+        assert_eq!(database.get_frozen_submissions(contest).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn windowed_contest() {
+        static SUBMIT_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO submissions (user_id, contest_id, problem_id, code, result, tests_done, submitted_at) VALUES ($1, $2, $3, '', $4, 0, $5)");
+
+        let database = create_temporary_database("windowed").await;
+
+        let contest = database.add_contest("contest").await.unwrap();
+        database.set_contest_type(contest, ContestType::Icpc).await.unwrap();
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let minutes = |minutes| start + Duration::try_minutes(minutes).unwrap();
+        let times = ContestTimes {
+            start_time: Some(start),
+            end_time: Some(minutes(600)),
+            freeze_time: None,
+            duration_minutes: Some(120),
+        };
+        database.set_contest_times(contest, times).await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        database.add_problem_to_contest(contest, problem).await.unwrap();
+        let early = database.add_user("early", "password", false).await.unwrap();
+        let late = database.add_user("late", "password", false).await.unwrap();
+        database.add_user_to_contest(early, contest).await.unwrap();
+        database.add_user_to_contest(late, contest).await.unwrap();
+
+        assert_eq!(database.get_user_contest_times(early, contest).await.unwrap(), None);
+        database.start_participation(early, contest, minutes(10)).await.unwrap();
+        database.start_participation(late, contest, minutes(550)).await.unwrap();
+        // starting again does not move the start
+        database.start_participation(early, contest, minutes(20)).await.unwrap();
+
+        let early_times = database.get_user_contest_times(early, contest).await.unwrap().unwrap();
+        assert_eq!((early_times.start_time, early_times.end_time), (Some(minutes(10)), Some(minutes(130))));
+        // the personal time ends with the window
+        let late_times = database.get_user_contest_times(late, contest).await.unwrap().unwrap();
+        assert_eq!(late_times.end_time, Some(minutes(600)));
+
+        for (user, minute) in [(early, 40), (early, 140), (late, 560)] {
+            SUBMIT_QUERY
+                .execute(&database, &[&user, &contest, &problem, &testing_result_to_i32(TestingResult::Accepted), &minutes(minute)])
+                .await
+                .unwrap();
+        }
+        let scoreboard = database.get_scoreboard(contest, None).await.unwrap();
+        let rows = scoreboard
+            .rows
+            .iter()
+            .map(|row| (row.username.as_str(), row.cells.first().unwrap().solve_minute, row.elapsed_minutes()))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, [("late", Some(10), Some(10)), ("early", Some(30), Some(30))]);
+
+        // an extension gives participants that already started more time as well
+        database.set_contest_times(contest, times.extended_by(30).unwrap()).await.unwrap();
+        let early_times = database.get_user_contest_times(early, contest).await.unwrap().unwrap();
+        assert_eq!(early_times.end_time, Some(minutes(160)));
+        let late_times = database.get_user_contest_times(late, contest).await.unwrap().unwrap();
+        assert_eq!(late_times.end_time, Some(minutes(630)));
+    }
+
+    #[tokio::test]
+    async fn clarifications() {
+        let database = create_temporary_database("clarifications").await;
+
+        let asker = database.add_user("asker", "password", false).await.unwrap();
+        let other = database.add_user("other", "password", false).await.unwrap();
+        let contest = database.add_contest("contest").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        database.add_problem_to_contest(contest, problem).await.unwrap();
+        database.add_user_to_contest(asker, contest).await.unwrap();
+        database.add_user_to_contest(other, contest).await.unwrap();
+
+        let private = database.add_clarification(asker, contest, Some(problem), "private").await.unwrap();
+        let public = database.add_clarification(asker, contest, None, "public").await.unwrap();
+        // unanswered questions are only seen by the asker and the admins
+        assert_eq!(database.get_clarifications(contest, Some(asker)).await.unwrap().len(), 2);
+        assert!(database.get_clarifications(contest, Some(other)).await.unwrap().is_empty());
+        assert_eq!(database.get_clarifications(contest, None).await.unwrap().first().unwrap().problem, "");
+
+        assert!(database.answer_clarification(contest, private, "yes", false).await.unwrap());
+        assert!(database.answer_clarification(contest, public, "no", true).await.unwrap());
+        assert!(!database.answer_clarification(contest, public, "again", true).await.unwrap());
+
+        for (user, expected) in [(asker, vec!["public", "private"]), (other, vec!["public"])] {
+            let clarifications = database.get_clarifications(contest, Some(user)).await.unwrap();
+            assert_eq!(clarifications.iter().map(|clarification| clarification.question.as_str()).collect::<Vec<_>>(), expected);
+        }
+        assert_eq!(database.get_clarifications(contest, None).await.unwrap().last().unwrap().problem, "A. problem");
+
+        assert_eq!(database.get_new_answer_counts(asker).await.unwrap().get(&contest), Some(&2));
+        assert_eq!(database.get_new_answer_counts(other).await.unwrap().get(&contest), Some(&1));
+        database.mark_clarifications_seen(other, contest, Utc::now()).await.unwrap();
+        assert!(database.get_new_answer_counts(other).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn announcements() {
+        let database = create_temporary_database("announcements").await;
+
+        let user = database.add_user("user", "password", false).await.unwrap();
+        let contest = database.add_contest("contest").await.unwrap();
+        let first = database.add_problem("first", "description", 1000).await.unwrap();
+        let second = database.add_problem("second", "description", 1000).await.unwrap();
+        database.add_problem_to_contest(contest, first).await.unwrap();
+        database.add_problem_to_contest(contest, second).await.unwrap();
+
+        database.add_announcement(contest, None, "general").await.unwrap();
+        database.add_announcement(contest, Some(first), "first").await.unwrap();
+        database.add_announcement(contest, Some(second), "second").await.unwrap();
+
+        // a problem page only shows the announcements about the whole contest and that problem
+        let announcements = database.get_announcements(contest, Some(first), user).await.unwrap();
+        assert_eq!(
+            announcements.iter().map(|announcement| (announcement.text.as_str(), announcement.problem.as_str())).collect::<Vec<_>>(),
+            [("first", "A. first"), ("general", "")]
+        );
+        assert!(announcements.iter().all(|announcement| announcement.is_new));
+
+        let read = announcements.iter().map(|announcement| announcement.announcement_id).collect::<Vec<_>>();
+        database.mark_announcements_read(user, &read).await.unwrap();
+        database.mark_announcements_read(user, &read).await.unwrap();
+        let announcements = database.get_announcements(contest, None, user).await.unwrap();
+        assert_eq!(announcements.iter().map(|announcement| announcement.is_new).collect::<Vec<_>>(), [true, false, false]);
+    }
+
+    #[tokio::test]
+    async fn teams() {
+        static SUBMIT_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "INSERT INTO submissions (user_id, submitted_by, contest_id, problem_id, code, result, tests_done, submitted_at) VALUES ($1, $2, $3, $4, '', $5, 0, $6) RETURNING submission_id",
+        );
+        static POINTS_QUERY: DatabaseQuery = DatabaseQuery::new("INSERT INTO subtask_results (submission_id, subtask_id, result, points) VALUES ($1, $2, $3, $4)");
+
+        let database = create_temporary_database("teams").await;
+
+        let admin = database.add_user("admin", "password", true).await.unwrap();
+        let first = database.add_user("first", "password", false).await.unwrap();
+        let second = database.add_user("second", "password", false).await.unwrap();
+        let team = database.add_team("team", "password").await.unwrap();
+        database.set_user_team(first, Some(team)).await.unwrap();
+        database.set_user_team(second, Some(team)).await.unwrap();
+        database.set_user_team(admin, Some(team)).await.unwrap_err();
+        database.set_user_team(team, Some(team)).await.unwrap_err();
+        database.set_user_team(first, Some(second)).await.unwrap_err();
+        assert_eq!(database.get_acting_user(first).await.unwrap(), team);
+        assert_eq!(database.get_acting_user(team).await.unwrap(), team);
+        assert!(database.try_login("team", "password").await.unwrap().is_some());
+
+        let contest = database.add_contest("contest").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        let subtask = database.add_subtask(problem, 40).await.unwrap();
+        database.add_problem_to_contest(contest, problem).await.unwrap();
+        database.add_user_to_contest(team, contest).await.unwrap();
+        let participants = database.get_all_users_with_participation(contest).await.unwrap();
+        assert_eq!(participants.iter().map(|participant| participant.1.as_str()).collect::<Vec<_>>(), ["admin", "team"]);
+
+        // members only participate through their team, so nobody joins a team while participating on their own
+        database.add_user_to_contest(first, contest).await.unwrap_err();
+        let third = database.add_user("third", "password", false).await.unwrap();
+        database.add_user_to_contest(third, contest).await.unwrap();
+        database.set_user_team(third, Some(team)).await.unwrap_err();
+        database.remove_user_from_contest(third, contest).await.unwrap();
+        database.set_user_team(third, Some(team)).await.unwrap();
+
+        // both members add to the score of the team
+        let accepted = testing_result_to_i32(TestingResult::Accepted);
+        let time = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        for (member, points) in [(first, 10), (second, 40)] {
+            let rows = SUBMIT_QUERY.execute(&database, &[&team, &member, &contest, &problem, &accepted, &time]).await.unwrap();
+            let submission: i32 = rows.first().unwrap().get(0);
+            POINTS_QUERY.execute(&database, &[&submission, &subtask, &accepted, &points]).await.unwrap();
+            database.update_submission_result(submission).await.unwrap();
+        }
+        let scoreboard = database.get_scoreboard(contest, None).await.unwrap();
+        let rows = scoreboard.rows.iter().map(|row| (row.username.as_str(), row.total)).collect::<Vec<_>>();
+        assert_eq!(rows, [("team", 40)]);
+        assert_eq!(database.get_submission_summaries_for_user_problem(team, contest, problem).await.unwrap().len(), 2);
+
+        // the members act as themselves again once the team is gone
+        database.delete_user(team).await.unwrap();
+        assert_eq!(database.get_acting_user(first).await.unwrap(), first);
+    }
+
+    #[tokio::test]
+    async fn problem_access() {
+        let database = create_temporary_database("access").await;
+
+        let admin = database.add_user("admin", "password", true).await.unwrap();
+        let user = database.add_user("user", "password", false).await.unwrap();
+        let windowed = database.add_contest("windowed").await.unwrap();
+        let times = ContestTimes {
+            start_time: Some(Utc::now() - Duration::try_hours(1).unwrap()),
+            end_time: Some(Utc::now() + Duration::try_hours(1).unwrap()),
+            freeze_time: None,
+            duration_minutes: Some(30),
+        };
+        database.set_contest_times(windowed, times).await.unwrap();
+        let open = database.add_contest("open").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        database.add_problem_to_contest(windowed, problem).await.unwrap();
+        database.add_user_to_contest(user, windowed).await.unwrap();
+        database.add_user_to_contest(user, open).await.unwrap();
+
+        // the problem cannot be reached through a contest without a window that it is not part of
+        assert!(!can_access_problem(&database, user, open, problem).await.unwrap());
+        assert!(create_problem_page(&database, &open.to_string(), &problem.to_string(), user, String::new()).await.unwrap().is_none());
+        assert!(can_access_problem(&database, user, windowed, problem).await.unwrap());
+        assert!(can_access_problem(&database, admin, windowed, problem).await.unwrap());
+
+        // the window only applies to its own contest, a problem that is also part of another contest of the user is open there
+        database.add_problem_to_contest(open, problem).await.unwrap();
+        assert_eq!(database.get_user_contest_times(user, windowed).await.unwrap(), None);
+        assert!(can_access_problem(&database, user, open, problem).await.unwrap());
+
+        // only participants reach the problems of a contest, and only they have times in it
+        database.remove_user_from_contest(user, windowed).await.unwrap();
+        database.remove_user_from_contest(user, open).await.unwrap();
+        assert!(!can_access_problem(&database, user, windowed, problem).await.unwrap());
+        assert_eq!(database.get_user_contest_times(user, open).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn submission_access() {
+        static SUBMIT_QUERY: DatabaseQuery = DatabaseQuery::new(
+            "INSERT INTO submissions (user_id, submitted_by, contest_id, problem_id, code, result, tests_done, submitted_at) VALUES ($1, $2, $3, $4, '', $5, 0, $6) RETURNING submission_id",
+        );
+
+        let database = create_temporary_database("submission_access").await;
+
+        let admin = database.add_user("admin", "password", true).await.unwrap();
+        let owner = database.add_user("owner", "password", false).await.unwrap();
+        let other = database.add_user("other", "password", false).await.unwrap();
+        let member = database.add_user("member", "password", false).await.unwrap();
+        let team = database.add_team("team", "password").await.unwrap();
+        database.set_user_team(member, Some(team)).await.unwrap();
+        let contest = database.add_contest("contest").await.unwrap();
+        let problem = database.add_problem("problem", "description", 1000).await.unwrap();
+        let other_problem = database.add_problem("other problem", "description", 1000).await.unwrap();
+        database.add_problem_to_contest(contest, problem).await.unwrap();
+        database.add_problem_to_contest(contest, other_problem).await.unwrap();
+
+        let accepted = testing_result_to_i32(TestingResult::Accepted);
+        let mut submissions = Vec::new();
+        for user in [owner, team] {
+            let rows = SUBMIT_QUERY.execute(&database, &[&user, &user, &contest, &problem, &accepted, &Utc::now()]).await.unwrap();
+            submissions.push(rows.first().unwrap().get::<i32>(0).to_string());
+        }
+        let [by_owner, by_team]: [String; 2] = submissions.try_into().unwrap();
+        let (contest, problem, other_problem) = (contest.to_string(), problem.to_string(), other_problem.to_string());
+
+        // only admins and the owner see a submission, team members see the submissions of their team
+        assert!(create_submission_page(&database, &contest, &problem, &by_owner, owner).await.unwrap().is_some());
+        assert!(create_submission_page(&database, &contest, &problem, &by_owner, admin).await.unwrap().is_some());
+        assert!(create_submission_page(&database, &contest, &problem, &by_owner, other).await.unwrap().is_none());
+        assert!(create_submission_page(&database, &contest, &problem, &by_team, member).await.unwrap().is_some());
+        assert!(create_submission_page(&database, &contest, &problem, &by_owner, member).await.unwrap().is_none());
+
+        // the submission is only found under the contest and problem it was submitted to
+        assert!(create_submission_page(&database, &contest, &other_problem, &by_owner, owner).await.unwrap().is_none());
+        assert!(create_submission_page(&database, "0", &problem, &by_owner, admin).await.unwrap().is_none());
+    }
+}
@@ -1,7 +1,7 @@
-use crate::database::user::UserToken;
+use crate::database::user::{UserId, UserToken};
 use crate::database::Database;
 use crate::request_handler::{create_html_response, RedirectSite};
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use askama::Template;
 use http_body_util::BodyExt;
 use http_body_util::Full;
@@ -9,6 +9,7 @@ use hyper::body::{Bytes, Incoming};
 use hyper::header::SET_COOKIE;
 use hyper::{Request, Response};
 use std::collections::HashMap;
+use std::net::IpAddr;
 
 #[derive(Template)]
 #[template(path = "login.html")]
@@ -31,6 +32,26 @@ pub fn parse_body(body: &str) -> HashMap<String, String> {
     map
 }
 
+/// decodes a value of an urlencoded form, `+` is a space and `%XX` is an escaped byte.
+/// `parse_body` keeps the values as they were sent, because passwords were always hashed that way.
+pub fn decode_form_value(value: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [input.next().unwrap_or(0), input.next().unwrap_or(0)];
+                if let Some(decoded) = std::str::from_utf8(&hex).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    bytes.push(decoded);
+                }
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
 pub fn get_login_token(request: &Request<Incoming>) -> Result<Option<UserToken>> {
     let cookie = request.headers().get("cookie");
     if let Some(cookie) = cookie {
@@ -99,7 +120,7 @@ pub fn create_login_page() -> Result<Response<Full<Bytes>>> {
     create_html_response(&LoginSite { error_message: String::new() })
 }
 
-pub async fn handle_user_creation(database: &Database, request: Request<Incoming>, is_admin: bool) -> Result<Response<Full<Bytes>>> {
+pub async fn handle_user_creation(database: &Database, request: Request<Incoming>, user: UserId, ip: IpAddr, is_admin: bool) -> Result<Response<Full<Bytes>>> {
     if !is_admin {
         return create_html_response(&LoginSite {
             error_message: "You must be an admin to perform this action".to_owned(),
@@ -115,15 +136,94 @@ pub async fn handle_user_creation(database: &Database, request: Request<Incoming
     let username = parsed_body.get("username").ok_or_else(|| anyhow!("Username not found"))?;
     let password = parsed_body.get("password").ok_or_else(|| anyhow!("Password not found"))?;
     let is_admin = parsed_body.get("is_admin").is_some_and(|x| x == "on");
+    let is_team = parsed_body.get("is_team").is_some_and(|x| x == "on");
+    if is_admin && is_team {
+        bail!("A team cannot be an admin");
+    }
 
-    database.add_user(username, password, is_admin).await?;
+    let actor = database.get_audit_actor(user, ip).await?;
+    let transaction = database.begin_transaction().await?;
+    let user_id = if is_team {
+        transaction.add_team(username, password).await?
+    } else {
+        transaction.add_user(username, password, is_admin).await?
+    };
+    transaction
+        .add_audit_entry(&actor, "create_user", &format!("user {user_id}"), "", &format!("{username}, admin: {is_admin}, team: {is_team}"))
+        .await?;
+    transaction.commit().await?;
 
     Ok(response)
 }
 
-pub async fn delete_user(database: &Database, user_id: &str) -> Result<Response<Full<Bytes>>> {
+async fn get_team_summary(database: &Database, user_id: UserId) -> Result<String> {
+    Ok(match database.get_user_team(user_id).await? {
+        Some(team_id) => database.get_username(team_id).await?.unwrap_or_default(),
+        None => String::new(),
+    })
+}
+
+/// adds a user to a team or removes them from it if no team is given
+pub async fn handle_team_assignment(database: &Database, user_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Response<Full<Bytes>>> {
     let user_id = user_id.parse()?;
 
-    database.delete_user(user_id).await?;
+    let body = request.into_body().collect().await?.to_bytes();
+    let body = String::from_utf8_lossy(&body).to_string();
+    let parsed_body = parse_body(&body);
+
+    let team_id = match parsed_body.get("team").map(String::as_str) {
+        None | Some("") => None,
+        Some(team_id) => Some(team_id.parse()?),
+    };
+
+    let actor = database.get_audit_actor(user, ip).await?;
+    let transaction = database.begin_transaction().await?;
+    let before = get_team_summary(&transaction, user_id).await?;
+    transaction.set_user_team(user_id, team_id).await?;
+    let after = get_team_summary(&transaction, user_id).await?;
+    transaction.add_audit_entry(&actor, "set_team", &format!("user {user_id}"), &before, &after).await?;
+    transaction.commit().await?;
+
     create_html_response(&RedirectSite { url: "/".to_owned() })
 }
+
+/// the form has to be submitted with the confirmation checkbox ticked
+pub async fn handle_user_deletion(database: &Database, user_id: &str, user: UserId, ip: IpAddr, request: Request<Incoming>) -> Result<Response<Full<Bytes>>> {
+    let user_id = user_id.parse()?;
+
+    let body = request.into_body().collect().await?.to_bytes();
+    let body = String::from_utf8_lossy(&body).to_string();
+    if parse_body(&body).get("confirm").map(String::as_str) == Some("on") {
+        delete_user(database, user_id, user, ip).await?;
+    }
+    create_html_response(&RedirectSite { url: "/".to_owned() })
+}
+
+/// deletes the user and records it in the audit log, the admin can also delete themselves
+pub async fn delete_user(database: &Database, user_id: UserId, user: UserId, ip: IpAddr) -> Result<()> {
+    if let Some(username) = database.get_username(user_id).await? {
+        let actor = database.get_audit_actor(user, ip).await?;
+        let before = format!("{username}, admin: {}", database.is_user_admin(user_id).await?);
+        // the entry is written first, so it still refers to an existing admin when they delete themselves
+        let transaction = database.begin_transaction().await?;
+        transaction.add_audit_entry(&actor, "delete_user", &format!("user {user_id}"), &before, "").await?;
+        transaction.delete_user(user_id).await?;
+        transaction.commit().await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_form_value;
+
+    #[test]
+    fn form_values() {
+        assert_eq!(decode_form_value("Contest+1%21"), "Contest 1!");
+        assert_eq!(decode_form_value("a%2Bb+%26+c"), "a+b & c");
+        assert_eq!(decode_form_value("%C3%A9t%C3%A9"), "\u{e9}t\u{e9}");
+        // broken escapes are dropped instead of failing
+        assert_eq!(decode_form_value("100%"), "100");
+        assert_eq!(decode_form_value("%zz1"), "1");
+    }
+}
@@ -2,17 +2,130 @@ use crate::database::submission::{SubmissionId, TestingResult};
 use crate::database::test::TestId;
 use crate::database::Database;
 use crate::tester::execute_test;
-use anyhow::Result;
+use crate::Config;
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::atomic::{AtomicI32, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
 use tokio::sync::mpsc;
-use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::sync::mpsc::{Receiver, Sender, UnboundedReceiver, UnboundedSender};
+use tokio::sync::OwnedMutexGuard;
+use tokio::task::JoinHandle;
 
 const BUFFER_SIZE: usize = 255;
+// how long finished submissions are remembered for the throughput statistics
+const STATISTICS_WINDOW: Duration = Duration::from_mins(10);
+// how many minutes of throughput are reported in the judge status
+const STATISTICS_MINUTES: u64 = 5;
+// how often an idle worker reports that it is alive
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+// an idle worker that has not reported for this long is considered dead
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(30);
+// how often the supervisor checks the workers
+const SUPERVISOR_INTERVAL: Duration = Duration::from_secs(1);
+// a job that kills this many workers is given up on and marked as an internal error
+const MAX_JOB_ATTEMPTS: u32 = 2;
+
+/// a single test of a submission that is sent to a worker
+#[derive(Clone)]
+struct WorkerJob {
+    submission_id: SubmissionId,
+    test_id: TestId,
+    executable: PathBuf,
+    // how long the job can run before its worker is restarted
+    timeout: Duration,
+    // how many times this job was already given to a worker that died while running it
+    attempts: u32,
+}
+
+/// the job a worker is currently running and when it started running it
+type RunningJob = (WorkerJob, Instant);
+
+/// statistics about submissions that pass through the judge, shared by all workers
+#[derive(Default)]
+struct JudgeStatistics {
+    // submissions that are waiting in the compile queue or are being compiled, with the time they were queued
+    compiling: Mutex<BTreeMap<SubmissionId, Instant>>,
+    // submissions that are not fully judged yet, with the time they were queued
+    pending: Mutex<BTreeMap<SubmissionId, Instant>>,
+    // when each recently judged submission finished and how long it took
+    finished: Mutex<VecDeque<(Instant, Duration)>>,
+}
+
+impl JudgeStatistics {
+    fn submission_queued(&self, submission_id: SubmissionId) -> Result<()> {
+        let now = Instant::now();
+        self.pending.lock().map_err(|e| anyhow!("{e}"))?.insert(submission_id, now);
+        self.compiling.lock().map_err(|e| anyhow!("{e}"))?.insert(submission_id, now);
+        Ok(())
+    }
+
+    fn submission_compiled(&self, submission_id: SubmissionId) -> Result<()> {
+        self.compiling.lock().map_err(|e| anyhow!("{e}"))?.remove(&submission_id);
+        Ok(())
+    }
+
+    fn submission_finished(&self, submission_id: SubmissionId) -> Result<()> {
+        self.submission_compiled(submission_id)?;
+        let Some(queued) = self.pending.lock().map_err(|e| anyhow!("{e}"))?.remove(&submission_id) else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let mut finished = self.finished.lock().map_err(|e| anyhow!("{e}"))?;
+        finished.push_back((now, now.duration_since(queued)));
+        while finished.front().is_some_and(|(time, _)| now.duration_since(*time) > STATISTICS_WINDOW) {
+            finished.pop_front();
+        }
+        drop(finished);
+        Ok(())
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct RunningTestStatus {
+    pub submission_id: SubmissionId,
+    pub test_id: TestId,
+    pub running_ms: u128,
+}
+
+#[derive(serde::Serialize)]
+pub struct WorkerStatus {
+    pub worker_id: usize,
+    pub queue_size: i32,
+    pub current_test: Option<RunningTestStatus>,
+    pub last_heartbeat_ms: u128,
+    pub restarts: i32,
+}
+
+#[derive(serde::Serialize)]
+pub struct PendingSubmissionStatus {
+    pub submission_id: SubmissionId,
+    pub waiting_ms: u128,
+}
+
+#[derive(serde::Serialize)]
+pub struct ThroughputStatus {
+    pub minutes_ago: u64,
+    pub submissions_judged: usize,
+    pub average_latency_ms: u128,
+}
+
+/// a snapshot of what the judge is doing, used by the judge status page and its api endpoint
+#[derive(serde::Serialize)]
+pub struct JudgeStatus {
+    pub workers: Vec<WorkerStatus>,
+    pub compile_workers: i32,
+    pub compile_queue_size: i32,
+    pub compiling: Vec<PendingSubmissionStatus>,
+    pub throughput: Vec<ThroughputStatus>,
+    pub oldest_pending: Option<PendingSubmissionStatus>,
+}
 
 async fn worker_do_test(database: &Database, submission_id: SubmissionId, test_id: TestId, executable: &Path, worker_id: i32) -> Result<()> {
     database.set_test_result(submission_id, test_id, TestingResult::Testing).await?;
@@ -30,46 +143,151 @@ async fn worker_do_test(database: &Database, submission_id: SubmissionId, test_i
     Ok(())
 }
 
-async fn worker_test_is_done(database: &Database, submission_id: SubmissionId, executable: &Path, queue_size: &Arc<AtomicI32>) -> Result<()> {
-    queue_size.fetch_sub(1, Ordering::SeqCst);
-    database.increment_submission_tests_done(submission_id).await?;
-    let tests_done = database.get_submission_tests_done(submission_id).await?;
-    let total_tests = database.get_tests_for_submission(submission_id).await?.len() as i32;
-    if tests_done == total_tests {
-        database.update_submission_result(submission_id).await?;
+/// one lock per submission, so that counting the finished tests of a submission and resetting a test for a retry do not interleave,
+/// while tests of different submissions are counted at the same time
+#[derive(Clone, Default)]
+struct SubmissionLocks(Arc<Mutex<HashMap<SubmissionId, Arc<tokio::sync::Mutex<()>>>>>);
+
+impl SubmissionLocks {
+    async fn lock(&self, submission_id: SubmissionId) -> Result<SubmissionGuard> {
+        let lock = self.0.lock().map_err(|e| anyhow!("{e}"))?.entry(submission_id).or_default().clone();
+        Ok(SubmissionGuard {
+            guard: Some(lock.lock_owned().await),
+            locks: self.clone(),
+            submission_id,
+        })
+    }
+}
+
+/// removes the lock of the submission once nobody holds or waits for it
+struct SubmissionGuard {
+    guard: Option<OwnedMutexGuard<()>>,
+    locks: SubmissionLocks,
+    submission_id: SubmissionId,
+}
+
+impl Drop for SubmissionGuard {
+    fn drop(&mut self) {
+        let Some(guard) = self.guard.take() else {
+            return;
+        };
+        let lock = OwnedMutexGuard::mutex(&guard).clone();
+        drop(guard);
+        // new waiters clone the lock while holding the map, so nobody can start waiting in between
+        if let Ok(mut locks) = self.locks.0.lock() {
+            // one reference is in the map and one is ours
+            if Arc::strong_count(&lock) == 2 {
+                locks.remove(&self.submission_id);
+            }
+        }
+    }
+}
+
+async fn worker_test_is_done(state: &WorkerState, job: &WorkerJob) -> Result<()> {
+    let database = &state.database;
+    state.queue_size.fetch_sub(1, Ordering::SeqCst);
+
+    let _submission_guard = state.submission_locks.lock(job.submission_id).await?;
+    let previous_tests_done = database.get_submission_tests_done(job.submission_id).await?;
+    let tests_done = database.update_submission_tests_done(job.submission_id).await?;
+    let total_tests = database.get_tests_for_submission(job.submission_id).await?.len() as i32;
+    // only the worker that finishes the last test finishes the submission
+    if tests_done == total_tests && previous_tests_done != total_tests {
+        database.update_submission_result(job.submission_id).await?;
+        state.statistics.submission_finished(job.submission_id)?;
         // delete the executable if it exists
-        tokio::fs::remove_file(executable).await.ok();
+        tokio::fs::remove_file(&job.executable).await.ok();
     }
     Ok(())
 }
 
-async fn worker(mut receiver: Receiver<(SubmissionId, TestId, PathBuf)>, queue_size: Arc<AtomicI32>, database: Database, worker_id: i32) -> ! {
+/// everything a worker task needs, kept by the manager so that a dead task can be replaced
+#[derive(Clone)]
+struct WorkerState {
+    receiver: Arc<tokio::sync::Mutex<Receiver<WorkerJob>>>,
+    queue_size: Arc<AtomicI32>,
+    current_job: Arc<Mutex<Option<RunningJob>>>,
+    heartbeat: Arc<Mutex<Instant>>,
+    statistics: Arc<JudgeStatistics>,
+    // shared by all workers
+    submission_locks: SubmissionLocks,
+    database: Database,
+    worker_id: i32,
+}
+
+impl WorkerState {
+    fn beat(&self) {
+        if let Ok(mut heartbeat) = self.heartbeat.lock() {
+            *heartbeat = Instant::now();
+        }
+    }
+
+    fn set_current_job(&self, job: Option<RunningJob>) {
+        if let Ok(mut current_job) = self.current_job.lock() {
+            *current_job = job;
+        }
+    }
+}
+
+async fn run_worker(state: WorkerState) {
     loop {
-        if let Some((submission_id, test_id, executable)) = receiver.recv().await {
-            // execute the test
-
-            let res = worker_do_test(&database, submission_id, test_id, &executable, worker_id).await;
-            if let Err(e) = res {
-                eprintln!("Error while testing: {e}");
-                database.set_test_result(submission_id, test_id, TestingResult::InternalError).await.ok();
-                // ignore errors
+        state.beat();
+
+        let job = {
+            let mut receiver = state.receiver.lock().await;
+            match tokio::time::timeout(HEARTBEAT_INTERVAL, receiver.recv()).await {
+                Ok(Some(job)) => job,
+                // the manager is gone, so there will be no more jobs
+                Ok(None) => return,
+                // no job arrived, wait again after sending a heartbeat
+                Err(_) => continue,
             }
-            worker_test_is_done(&database, submission_id, &executable, &queue_size).await.ok();
+        };
+
+        // execute the test
+        state.set_current_job(Some((job.clone(), Instant::now())));
+
+        let res = worker_do_test(&state.database, job.submission_id, job.test_id, &job.executable, state.worker_id).await;
+        if let Err(e) = res {
+            eprintln!("Error while testing: {e}");
+            state.database.set_test_result(job.submission_id, job.test_id, TestingResult::InternalError).await.ok();
+            // ignore errors
         }
+        worker_test_is_done(&state, &job).await.ok();
+
+        state.set_current_job(None);
     }
 }
 
-fn spawn_worker(database: &Database, worker_id: i32) -> (Sender<(SubmissionId, TestId, PathBuf)>, Arc<AtomicI32>) {
-    let (sender, receiver) = mpsc::channel(BUFFER_SIZE);
-    let queue_size = Arc::new(AtomicI32::new(0));
-    let queue_size_clone = queue_size.clone();
+struct WorkerHandle {
+    sender: Sender<WorkerJob>,
+    state: WorkerState,
+    task: Mutex<Option<JoinHandle<()>>>,
+    restarts: AtomicI32,
+}
 
-    let database = database.clone();
-    tokio::spawn(async move {
-        worker(receiver, queue_size_clone, database, worker_id).await;
-    });
+fn spawn_worker(database: &Database, statistics: &Arc<JudgeStatistics>, submission_locks: &SubmissionLocks, worker_id: i32) -> WorkerHandle {
+    let (sender, receiver) = mpsc::channel(BUFFER_SIZE);
 
-    (sender, queue_size)
+    let state = WorkerState {
+        receiver: Arc::new(tokio::sync::Mutex::new(receiver)),
+        queue_size: Arc::new(AtomicI32::new(0)),
+        current_job: Arc::new(Mutex::new(None)),
+        heartbeat: Arc::new(Mutex::new(Instant::now())),
+        statistics: statistics.clone(),
+        submission_locks: submission_locks.clone(),
+        database: database.clone(),
+        worker_id,
+    };
+
+    let task = tokio::spawn(run_worker(state.clone()));
+
+    WorkerHandle {
+        sender,
+        state,
+        task: Mutex::new(Some(task)),
+        restarts: AtomicI32::new(0),
+    }
 }
 
 async fn compile_code(code: &str) -> Result<PathBuf> {
@@ -111,40 +329,174 @@ async fn compile_code(code: &str) -> Result<PathBuf> {
     Ok(Path::new(&output_name).to_owned())
 }
 
+/// takes submissions from the compile queue in order and compiles them, at most one at a time
+async fn run_compile_worker(receiver: Arc<tokio::sync::Mutex<UnboundedReceiver<SubmissionId>>>, manager: WorkerManager, database: Database) {
+    loop {
+        let Some(submission_id) = receiver.lock().await.recv().await else {
+            // the manager is gone, so there will be no more submissions
+            return;
+        };
+        manager.compile_queue_size.fetch_sub(1, Ordering::SeqCst);
+
+        if let Err(e) = manager.test_submission(submission_id, &database).await {
+            eprintln!("Error while testing submission {submission_id}: {e}");
+        }
+    }
+}
+
 #[derive(Clone)]
-#[allow(clippy::type_complexity)]
 pub struct WorkerManager {
-    workers: Arc<Vec<(Sender<(SubmissionId, TestId, PathBuf)>, Arc<AtomicI32>)>>,
+    workers: Arc<[WorkerHandle]>,
+    statistics: Arc<JudgeStatistics>,
+    job_timeout: Duration,
+    compile_sender: UnboundedSender<SubmissionId>,
+    compile_queue_size: Arc<AtomicI32>,
+    compile_workers: i32,
 }
 
 impl WorkerManager {
-    pub fn new(worker_count: usize, database: &Database) -> Self {
+    pub fn new(config: &Config, database: &Database) -> Self {
+        let statistics = Arc::new(JudgeStatistics::default());
+        let submission_locks = SubmissionLocks::default();
         let mut workers = Vec::new();
-        for worker_id in 0..worker_count {
-            workers.push(spawn_worker(database, worker_id as i32));
+        for worker_id in 0..config.num_workers {
+            workers.push(spawn_worker(database, &statistics, &submission_locks, worker_id));
+        }
+
+        let (compile_sender, compile_receiver) = mpsc::unbounded_channel();
+
+        let manager = Self {
+            workers: workers.into(),
+            statistics,
+            job_timeout: Duration::from_secs(config.job_timeout),
+            compile_sender,
+            compile_queue_size: Arc::new(AtomicI32::new(0)),
+            compile_workers: config.num_compile_workers,
+        };
+
+        // all compile workers share one queue, so submissions are compiled in the order they came in
+        let compile_receiver = Arc::new(tokio::sync::Mutex::new(compile_receiver));
+        for _ in 0..config.num_compile_workers {
+            tokio::spawn(run_compile_worker(compile_receiver.clone(), manager.clone(), database.clone()));
         }
-        Self { workers: Arc::new(workers) }
+
+        let supervisor = manager.clone();
+        tokio::spawn(async move {
+            supervisor.supervise().await;
+        });
+
+        manager
     }
 
-    async fn execute_test(&self, submission_id: SubmissionId, test_id: TestId, executable: PathBuf) -> Result<()> {
+    /// puts the submission into the compile queue, it will be tested after it is compiled
+    pub fn queue_submission(&self, submission_id: SubmissionId) -> Result<()> {
+        self.statistics.submission_queued(submission_id)?;
+        self.compile_queue_size.fetch_add(1, Ordering::SeqCst);
+        self.compile_sender.send(submission_id)?;
+        Ok(())
+    }
+
+    async fn dispatch_job(&self, job: WorkerJob) -> Result<()> {
         let mut min_queue_size = i32::MAX;
         let mut min_queue_index = 0;
-        for (i, (_sender, queue_size)) in self.workers.iter().enumerate() {
-            let queue_size = queue_size.load(Ordering::SeqCst);
+        for (i, worker) in self.workers.iter().enumerate() {
+            let queue_size = worker.state.queue_size.load(Ordering::SeqCst);
             if queue_size < min_queue_size {
                 min_queue_size = queue_size;
                 min_queue_index = i;
             }
         }
 
-        let (sender, queue_size) = &self.workers.get(min_queue_index).ok_or_else(|| anyhow::anyhow!("No workers"))?;
-        queue_size.fetch_add(1, Ordering::SeqCst);
-        sender.send((submission_id, test_id, executable)).await?;
+        let worker = self.workers.get(min_queue_index).ok_or_else(|| anyhow::anyhow!("No workers"))?;
+        worker.state.queue_size.fetch_add(1, Ordering::SeqCst);
+        worker.sender.send(job).await?;
 
         Ok(())
     }
 
-    pub async fn test_submission(&self, submission_id: SubmissionId, database: &Database) -> Result<()> {
+    async fn execute_test(&self, submission_id: SubmissionId, test_id: TestId, executable: PathBuf, timeout: Duration) -> Result<()> {
+        self.dispatch_job(WorkerJob {
+            submission_id,
+            test_id,
+            executable,
+            timeout,
+            attempts: 0,
+        })
+        .await
+    }
+
+    async fn supervise(&self) -> ! {
+        loop {
+            tokio::time::sleep(SUPERVISOR_INTERVAL).await;
+
+            for worker in self.workers.iter() {
+                if let Err(e) = self.check_worker(worker).await {
+                    eprintln!("Error while supervising worker {}: {e}", worker.state.worker_id);
+                }
+            }
+        }
+    }
+
+    /// restarts the worker if its task has stopped, its job ran for too long or it stopped sending heartbeats
+    async fn check_worker(&self, worker: &WorkerHandle) -> Result<()> {
+        let task_running = worker.task.lock().map_err(|e| anyhow!("{e}"))?.as_ref().is_some_and(|task| !task.is_finished());
+        let job_started = worker.state.current_job.lock().map_err(|e| anyhow!("{e}"))?.as_ref().map(|(job, started)| (job.timeout, *started));
+        let last_heartbeat = *worker.state.heartbeat.lock().map_err(|e| anyhow!("{e}"))?;
+
+        let reason = if !task_running {
+            "its task stopped"
+        } else if job_started.is_some_and(|(timeout, started)| started.elapsed() > timeout) {
+            "its job timed out"
+        } else if job_started.is_none() && last_heartbeat.elapsed() > HEARTBEAT_TIMEOUT {
+            "it stopped sending heartbeats"
+        } else {
+            return Ok(());
+        };
+
+        eprintln!("Restarting worker {} because {reason}", worker.state.worker_id);
+
+        // make sure the old task is gone before touching its job, dropping it also kills the running test
+        let old_task = worker.task.lock().map_err(|e| anyhow!("{e}"))?.take();
+        if let Some(old_task) = old_task {
+            old_task.abort();
+            old_task.await.ok();
+        }
+
+        let job = worker.state.current_job.lock().map_err(|e| anyhow!("{e}"))?.take();
+
+        worker.state.beat();
+        *worker.task.lock().map_err(|e| anyhow!("{e}"))? = Some(tokio::spawn(run_worker(worker.state.clone())));
+        worker.restarts.fetch_add(1, Ordering::SeqCst);
+
+        if let Some((job, _started)) = job {
+            self.retry_job(worker, job).await?;
+        }
+
+        Ok(())
+    }
+
+    /// gives the job of a dead worker to another worker or marks it as an internal error if it failed too many times
+    async fn retry_job(&self, dead_worker: &WorkerHandle, mut job: WorkerJob) -> Result<()> {
+        let database = &dead_worker.state.database;
+        job.attempts += 1;
+
+        // the dead worker may have stored a result for the test and counted it already
+        {
+            let _submission_guard = dead_worker.state.submission_locks.lock(job.submission_id).await?;
+            database.reset_test_result(job.submission_id, job.test_id).await?;
+            database.update_submission_tests_done(job.submission_id).await?;
+        }
+
+        if job.attempts >= MAX_JOB_ATTEMPTS {
+            database.set_test_result(job.submission_id, job.test_id, TestingResult::InternalError).await?;
+            return worker_test_is_done(&dead_worker.state, &job).await;
+        }
+
+        dead_worker.state.queue_size.fetch_sub(1, Ordering::SeqCst);
+        self.dispatch_job(job).await
+    }
+
+    async fn test_submission(&self, submission_id: SubmissionId, database: &Database) -> Result<()> {
         database.set_submission_result(submission_id, TestingResult::Compiling).await?;
 
         let code = database.get_submission_code(submission_id).await?;
@@ -152,22 +504,134 @@ impl WorkerManager {
 
         if let Err(e) = exe {
             database.set_submission_result(submission_id, TestingResult::CompilationError).await?;
+            self.statistics.submission_finished(submission_id)?;
             eprintln!("Error while compiling: {e}");
             return Ok(());
         }
 
         let exe = exe?;
+        self.statistics.submission_compiled(submission_id)?;
 
         database.set_submission_result(submission_id, TestingResult::Testing).await?;
         for subtask in database.get_subtasks_for_submission(submission_id).await? {
             database.set_subtask_result(submission_id, subtask, TestingResult::Testing).await?;
         }
 
+        // the time limit is what a test may take at most, the job timeout is left for everything around it
+        let problem_id = database.get_submission_problem(submission_id).await?;
+        let time_limit = u64::try_from(database.get_problem_time_limit(problem_id).await?)?;
+        let timeout = Duration::from_millis(time_limit) + self.job_timeout;
+
         let tests = database.get_tests_for_submission(submission_id).await?;
+        if tests.is_empty() {
+            // no worker will ever finish this submission, so finish it here
+            database.update_submission_result(submission_id).await?;
+            self.statistics.submission_finished(submission_id)?;
+            tokio::fs::remove_file(&exe).await.ok();
+        }
+
         for test in tests {
-            self.execute_test(submission_id, test, exe.clone()).await?;
+            self.execute_test(submission_id, test, exe.clone(), timeout).await?;
         }
 
         Ok(())
     }
+
+    pub fn get_status(&self) -> Result<JudgeStatus> {
+        let now = Instant::now();
+
+        let mut workers = Vec::new();
+        for (worker_id, worker) in self.workers.iter().enumerate() {
+            let current_test = worker.state.current_job.lock().map_err(|e| anyhow!("{e}"))?.as_ref().map(|(job, started)| RunningTestStatus {
+                submission_id: job.submission_id,
+                test_id: job.test_id,
+                running_ms: now.duration_since(*started).as_millis(),
+            });
+            let last_heartbeat = *worker.state.heartbeat.lock().map_err(|e| anyhow!("{e}"))?;
+
+            workers.push(WorkerStatus {
+                worker_id,
+                queue_size: worker.state.queue_size.load(Ordering::SeqCst),
+                current_test,
+                last_heartbeat_ms: now.duration_since(last_heartbeat).as_millis(),
+                restarts: worker.restarts.load(Ordering::SeqCst),
+            });
+        }
+
+        let compiling = self
+            .statistics
+            .compiling
+            .lock()
+            .map_err(|e| anyhow!("{e}"))?
+            .iter()
+            .map(|(submission_id, queued)| PendingSubmissionStatus {
+                submission_id: *submission_id,
+                waiting_ms: now.duration_since(*queued).as_millis(),
+            })
+            .collect();
+
+        let oldest_pending = self
+            .statistics
+            .pending
+            .lock()
+            .map_err(|e| anyhow!("{e}"))?
+            .iter()
+            .min_by_key(|(_submission_id, queued)| **queued)
+            .map(|(submission_id, queued)| PendingSubmissionStatus {
+                submission_id: *submission_id,
+                waiting_ms: now.duration_since(*queued).as_millis(),
+            });
+
+        let finished = self.statistics.finished.lock().map_err(|e| anyhow!("{e}"))?.clone();
+        let mut throughput = Vec::new();
+        for minutes_ago in 0..STATISTICS_MINUTES {
+            let latencies = finished
+                .iter()
+                .filter(|(time, _latency)| now.duration_since(*time).as_secs() / 60 == minutes_ago)
+                .map(|(_time, latency)| latency.as_millis())
+                .collect::<Vec<_>>();
+
+            throughput.push(ThroughputStatus {
+                minutes_ago,
+                submissions_judged: latencies.len(),
+                average_latency_ms: latencies.iter().sum::<u128>() / (latencies.len() as u128).max(1),
+            });
+        }
+
+        Ok(JudgeStatus {
+            workers,
+            compile_workers: self.compile_workers,
+            compile_queue_size: self.compile_queue_size.load(Ordering::SeqCst),
+            compiling,
+            throughput,
+            oldest_pending,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests should panic on errors")]
+mod tests {
+    use super::SubmissionLocks;
+
+    #[tokio::test]
+    async fn submission_locks() {
+        let locks = SubmissionLocks::default();
+
+        let first = locks.lock(1).await.unwrap();
+        // another submission does not wait for the first one
+        let second = locks.lock(2).await.unwrap();
+        // the same submission does
+        let waiting = tokio::spawn({
+            let locks = locks.clone();
+            async move { drop(locks.lock(1).await.unwrap()) }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+
+        drop(first);
+        waiting.await.unwrap();
+        drop(second);
+        assert!(locks.0.lock().unwrap().is_empty());
+    }
 }